@@ -1,3 +1,4 @@
+use hal::delay::Delay;
 use hal::exti;
 use hal::exti::{ExtiLine, GpioLine};
 use hal::gpio::*;
@@ -6,9 +7,8 @@ use hal::prelude::*;
 use hal::rcc::Rcc;
 use hal::spi;
 
-use nb::block;
 use stm32l0xx_hal as hal;
-use sx12xx::{AntPinsMode, BoardBindings};
+use sx12xx::{AntPinsMode, AntennaSwitch, Hal, NoBusy, NoTimers, PaSelect, TcxoPin};
 
 type Uninitialized = Analog;
 
@@ -31,7 +31,28 @@ pub fn initialize_irq(
     dio0
 }
 
-pub type TcxoEn = gpioa::PA8<Output<PushPull>>;
+// as BOARD_TCXO_WAKEUP_TIME in Semtech's B-L072Z-LRWAN1 port
+const TCXO_WAKEUP_MS: u8 = 5;
+
+type SpiPort = hal::spi::Spi<
+    hal::pac::SPI1,
+    (
+        hal::gpio::gpiob::PB3<Uninitialized>,
+        hal::gpio::gpioa::PA6<Uninitialized>,
+        hal::gpio::gpioa::PA7<Uninitialized>,
+    ),
+>;
+
+pub type RadioHal = Hal<
+    SpiPort,
+    gpioa::PA15<Output<PushPull>>,
+    gpioc::PC0<Output<PushPull>>,
+    NoBusy,
+    Delay,
+    AntSw,
+    NoTimers,
+    TcxoPin<gpioa::PA8<Output<PushPull>>>,
+>;
 
 pub fn new(
     spi_peripheral: pac::SPI1,
@@ -44,112 +65,34 @@ pub fn new(
     rx: gpioa::PA1<Uninitialized>,
     tx_rfo: gpioc::PC2<Uninitialized>,
     tx_boost: gpioc::PC1<Uninitialized>,
-    tcxo_en_pin: Option<gpioa::PA8<Uninitialized>>,
-) -> BoardBindings {
-    let mut set_board_tcxo = None;
-    // store all of the necessary pins and peripherals into statics
-    // this is necessary as the extern C functions need access
-    // this is safe, thanks to ownership and because these statics are private
-    unsafe {
-        SPI = Some(spi_peripheral.spi(
-            (spi_sck, spi_miso, spi_mosi),
-            spi::MODE_0,
-            200_000.hz(),
-            rcc,
-        ));
-        SPI_NSS = Some(spi_nss_pin.into_push_pull_output());
-        RESET = Some(reset.into_push_pull_output());
-        ANT_SW = Some(AntennaSwitches::new(
-            rx.into_push_pull_output(),
-            tx_rfo.into_push_pull_output(),
-            tx_boost.into_push_pull_output(),
-        ));
-        if let Some(tcxo_en) = tcxo_en_pin {
-            EN_TCXO = Some(tcxo_en.into_push_pull_output());
-            set_board_tcxo = Some(set_tcxo as unsafe extern "C" fn(bool) -> u8);
-        }
-    };
-
-    BoardBindings {
-        reset: Some(radio_reset),
-        spi_in_out: Some(spi_in_out),
-        spi_nss: Some(spi_nss),
-        delay_ms: Some(delay_ms),
-        set_antenna_pins: Some(set_antenna_pins),
-        set_board_tcxo,
-        busy_pin_status: None,
-        reduce_power: None,
-    }
-}
-
-static mut EN_TCXO: Option<TcxoEn> = None;
-
-#[no_mangle]
-pub extern "C" fn set_tcxo(value: bool) -> u8 {
-    unsafe {
-        if let Some(pin) = &mut EN_TCXO {
-            if value {
-                pin.set_high().unwrap();
-            } else {
-                pin.set_low().unwrap();
-            }
-        }
-    }
-    6
-}
-
-type SpiPort = hal::spi::Spi<
-    hal::pac::SPI1,
-    (
-        hal::gpio::gpiob::PB3<Uninitialized>,
-        hal::gpio::gpioa::PA6<Uninitialized>,
-        hal::gpio::gpioa::PA7<Uninitialized>,
-    ),
->;
-static mut SPI: Option<SpiPort> = None;
-#[no_mangle]
-extern "C" fn spi_in_out(out_data: u8) -> u8 {
-    unsafe {
-        if let Some(spi) = &mut SPI {
-            spi.send(out_data).unwrap();
-            block!(spi.read()).unwrap()
-        } else {
-            0
-        }
-    }
-}
-
-static mut SPI_NSS: Option<gpioa::PA15<Output<PushPull>>> = None;
-#[no_mangle]
-extern "C" fn spi_nss(value: bool) {
-    unsafe {
-        if let Some(pin) = &mut SPI_NSS {
-            if value {
-                pin.set_high().unwrap();
-            } else {
-                pin.set_low().unwrap();
-            }
-        }
-    }
-}
-
-static mut RESET: Option<gpioc::PC0<Output<PushPull>>> = None;
-#[no_mangle]
-extern "C" fn radio_reset(value: bool) {
-    unsafe {
-        if let Some(pin) = &mut RESET {
-            if value {
-                pin.set_low().unwrap();
-            } else {
-                pin.set_high().unwrap();
-            }
-        }
-    }
-}
-
-#[no_mangle]
-extern "C" fn delay_ms(ms: u32) {
-    cortex_m::asm::delay(ms);
+    tcxo_en: gpioa::PA8<Uninitialized>,
+    delay: Delay,
+) -> RadioHal {
+    let spi = spi_peripheral.spi(
+        (spi_sck, spi_miso, spi_mosi),
+        spi::MODE_0,
+        200_000.hz(),
+        rcc,
+    );
+    let antenna = AntennaSwitches::new(
+        rx.into_push_pull_output(),
+        tx_rfo.into_push_pull_output(),
+        tx_boost.into_push_pull_output(),
+    );
+    Hal::new(
+        spi,
+        spi_nss_pin.into_push_pull_output(),
+        reset.into_push_pull_output(),
+        NoBusy,
+        delay,
+        antenna,
+    )
+    .pa_select(PaSelect::PaSelectPaBoost)
+    // the drivers power the TCXO on reset and switch RegTcxo over to it
+    .tcxo(TcxoPin::new(
+        tcxo_en.into_push_pull_output(),
+        TCXO_WAKEUP_MS,
+    ))
 }
 
 pub struct AntennaSwitches<Rx, TxRfo, TxBoost> {
@@ -197,23 +140,18 @@ type AntSw = AntennaSwitches<
     stm32l0xx_hal::gpio::gpioc::PC1<stm32l0xx_hal::gpio::Output<stm32l0xx_hal::gpio::PushPull>>,
 >;
 
-static mut ANT_SW: Option<AntSw> = None;
-
-pub extern "C" fn set_antenna_pins(mode: AntPinsMode, _power: u8) {
-    unsafe {
-        if let Some(ant_sw) = &mut ANT_SW {
-            match mode {
-                AntPinsMode::AntModeTx => {
-                    ant_sw.set_tx();
-                }
-                AntPinsMode::AntModeRx => {
-                    ant_sw.set_rx();
-                }
-                AntPinsMode::AntModeSleep => {
-                    ant_sw.set_sleep();
-                }
-                _ => (),
-            }
+impl<Rx, TxRfo, TxBoost> AntennaSwitch for AntennaSwitches<Rx, TxRfo, TxBoost>
+where
+    Rx: embedded_hal::digital::v2::OutputPin,
+    TxRfo: embedded_hal::digital::v2::OutputPin,
+    TxBoost: embedded_hal::digital::v2::OutputPin,
+{
+    fn set_mode(&mut self, mode: AntPinsMode, _power: u8) {
+        match mode {
+            AntPinsMode::AntModeTx => self.set_tx(),
+            AntPinsMode::AntModeRx => self.set_rx(),
+            AntPinsMode::AntModeSleep => self.set_sleep(),
+            _ => (),
         }
     }
 }
@@ -15,6 +15,7 @@ use lorawan_device::{
 };
 use rtic::app;
 use stm32l0xx_hal::{
+    delay::Delay,
     exti::Exti,
     exti::{ExtiLine, GpioLine},
     pac,
@@ -32,7 +33,8 @@ use sx12xx::{self, LorawanRadio, Sx12xx};
 mod bindings;
 pub use bindings::initialize_irq as initialize_radio_irq;
 pub use bindings::RadioIRQ;
-pub use bindings::TcxoEn;
+
+type Radio = LorawanRadio<bindings::RadioHal>;
 
 // catena-4610
 use stm32l0xx_hal::serial::USART1 as DebugUsart;
@@ -84,7 +86,7 @@ const APP: () = {
         buffer: [u8; 512],
         #[init(false)]
         ready_to_send: bool,
-        lorawan: Option<LorawanDevice<LorawanRadio, Crypto>>,
+        lorawan: Option<LorawanDevice<Radio, Crypto>>,
         #[init(TimerContext {
         target: 0,
         count: 0,
@@ -130,7 +132,8 @@ const APP: () = {
         // Configure the timer.
         let timer = device.TIM2.timer(1.khz(), &mut rcc);
 
-        let bindings = bindings::new(
+        let delay = Delay::new(ctx.core.SYST, rcc.clocks);
+        let radio_hal = bindings::new(
             device.SPI1,
             &mut rcc,
             gpiob.pb3,
@@ -141,10 +144,11 @@ const APP: () = {
             gpioa.pa1,
             gpioc.pc2,
             gpioc.pc1,
-            Some(gpioa.pa8), // None //use pa8 for catena
+            gpioa.pa8,
+            delay,
         );
 
-        let mut sx12xx = Sx12xx::new(sx12xx::Radio::sx1276(), bindings);
+        let mut sx12xx = Sx12xx::from_hal(sx12xx::Radio::sx1276(), radio_hal).unwrap();
         sx12xx.set_public_network(true);
 
         let lorawan = LorawanDevice::new(
@@ -183,7 +187,7 @@ const APP: () = {
     }
 
     #[task(capacity = 4, priority = 2, resources = [debug_uart, buffer, lorawan], spawn  = [lorawan_response])]
-    fn lorawan_event(ctx: lorawan_event::Context, event: LorawanEvent<'static, LorawanRadio>) {
+    fn lorawan_event(ctx: lorawan_event::Context, event: LorawanEvent<'static, Radio>) {
         let debug = ctx.resources.debug_uart;
 
         // The LoraWAN stack is a giant state machine which needs to mutate internally
@@ -231,7 +235,7 @@ const APP: () = {
     #[task(capacity = 4, priority = 2, resources = [debug_uart, timer_context, lorawan], spawn = [lorawan_event, send_ping])]
     fn lorawan_response(
         mut ctx: lorawan_response::Context,
-        response: Result<LorawanResponse, LorawanError<LorawanRadio>>,
+        response: Result<LorawanResponse, LorawanError<Radio>>,
     ) {
         let debug = ctx.resources.debug_uart;
         match response {
@@ -0,0 +1,74 @@
+// Time on air of a packet, computed without a radio (Semtech AN1200.13 and
+// the SX1276 datasheet, section 4.1.1.7), so that schedulers and duty-cycle
+// budgets can run on host and device alike. Results are in microseconds,
+// rounded up.
+use super::{LoRaBandwidth, LoRaCodingRate, LoRaSpreadingFactor};
+
+#[derive(Debug, Clone, Copy)]
+pub struct LoRaAirtime {
+    pub spreading_factor: LoRaSpreadingFactor,
+    pub bandwidth: LoRaBandwidth,
+    pub coding_rate: LoRaCodingRate,
+    // in symbols, excluding the 4.25 symbols the modem adds
+    pub preamble_len: u16,
+    pub implicit_header: bool,
+    pub crc_on: bool,
+    pub low_data_rate_optimize: bool,
+}
+
+impl LoRaAirtime {
+    pub fn time_on_air_us(&self, len: u8) -> u32 {
+        let sf = self.spreading_factor as i64;
+        let de = if self.low_data_rate_optimize { 1 } else { 0 };
+        let ih = if self.implicit_header { 1 } else { 0 };
+        let crc = if self.crc_on { 1 } else { 0 };
+
+        let numerator = 8 * len as i64 - 4 * sf + 28 + 16 * crc - 20 * ih;
+        let denominator = 4 * (sf - 2 * de);
+        let blocks = if numerator > 0 {
+            (numerator + denominator - 1) / denominator
+        } else {
+            0
+        };
+        let payload_symbols = 8 + blocks * (self.coding_rate as i64 + 4);
+
+        // counted in quarter symbols to keep the 4.25 preamble symbols exact
+        let quarter_symbols = (4 * self.preamble_len as i64 + 17 + 4 * payload_symbols) as u64;
+        let numerator = quarter_symbols * (1u64 << sf) * 1_000_000;
+        let denominator = 4 * bandwidth_hz(self.bandwidth);
+        ((numerator + denominator - 1) / denominator) as u32
+    }
+}
+
+// the drivers fall back to 125 kHz for the reserved setting
+fn bandwidth_hz(bandwidth: LoRaBandwidth) -> u64 {
+    match bandwidth {
+        LoRaBandwidth::_250KHZ => 250_000,
+        LoRaBandwidth::_500KHZ => 500_000,
+        LoRaBandwidth::_125KHZ | LoRaBandwidth::_RESERVED => 125_000,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FskAirtime {
+    // bits per second
+    pub datarate: u32,
+    // in bytes
+    pub preamble_len: u16,
+    pub sync_word_len: u8,
+    // fixed length packets carry no length byte
+    pub fix_len: bool,
+    // 2 byte CRC
+    pub crc_on: bool,
+}
+
+impl FskAirtime {
+    pub fn time_on_air_us(&self, len: u8) -> u32 {
+        let length_byte = if self.fix_len { 0 } else { 1 };
+        let crc = if self.crc_on { 2 } else { 0 };
+        let bytes =
+            self.preamble_len as u64 + self.sync_word_len as u64 + length_byte + len as u64 + crc;
+        let datarate = self.datarate.max(1) as u64;
+        ((8 * bytes * 1_000_000 + datarate - 1) / datarate) as u32
+    }
+}
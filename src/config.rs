@@ -0,0 +1,432 @@
+// Complete modem configurations, checked against the limits of the chip
+// family before anything is handed to the driver. Fields are public so that
+// the configuration in use can be read back with Sx12xx::get_tx_config and
+// get_rx_config; the methods of the same name build one up from new().
+use super::{
+    Error, Family, FskAirtime, LoRaAirtime, LoRaBandwidth, LoRaCodingRate, LoRaSpreadingFactor,
+    RxConfig, TxConfig,
+};
+use heapless::consts::*;
+use heapless::Vec;
+use sx12xx_sys::{RadioModems_t, RadioModems_t_MODEM_FSK, RadioModems_t_MODEM_LORA};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoRaConfig {
+    pub spreading_factor: LoRaSpreadingFactor,
+    pub bandwidth: LoRaBandwidth,
+    pub coding_rate: LoRaCodingRate,
+    // in symbols
+    pub preamble_len: u16,
+    // payload length of implicit header packets, None for explicit header
+    pub implicit_header: Option<u8>,
+    pub crc_on: bool,
+    pub iq_inverted: bool,
+    // None leaves it to the drivers, which turn it on for symbols of 16 ms
+    // and more
+    pub low_data_rate_optimize: Option<bool>,
+    // None keeps the one chosen by Sx12xx::set_public_network
+    pub sync_word: Option<u8>,
+    // symbols between frequency hops, None to stay on one channel
+    pub hop_period: Option<u8>,
+    // TX only
+    pub power: i8,
+    pub tx_timeout_ms: u32,
+    // RX only; symbol_timeout is ignored in continuous mode
+    pub symbol_timeout: u16,
+    pub continuous_rx: bool,
+}
+
+impl LoRaConfig {
+    pub fn new(
+        spreading_factor: LoRaSpreadingFactor,
+        bandwidth: LoRaBandwidth,
+        coding_rate: LoRaCodingRate,
+    ) -> LoRaConfig {
+        LoRaConfig {
+            spreading_factor,
+            bandwidth,
+            coding_rate,
+            preamble_len: 8,
+            implicit_header: None,
+            crc_on: true,
+            iq_inverted: false,
+            low_data_rate_optimize: None,
+            sync_word: None,
+            hop_period: None,
+            power: 14,
+            tx_timeout_ms: 3000,
+            symbol_timeout: 5,
+            continuous_rx: true,
+        }
+    }
+
+    pub fn preamble_len(mut self, preamble_len: u16) -> Self {
+        self.preamble_len = preamble_len;
+        self
+    }
+
+    pub fn implicit_header(mut self, payload_len: u8) -> Self {
+        self.implicit_header = Some(payload_len);
+        self
+    }
+
+    pub fn explicit_header(mut self) -> Self {
+        self.implicit_header = None;
+        self
+    }
+
+    pub fn crc_on(mut self, crc_on: bool) -> Self {
+        self.crc_on = crc_on;
+        self
+    }
+
+    pub fn iq_inverted(mut self, iq_inverted: bool) -> Self {
+        self.iq_inverted = iq_inverted;
+        self
+    }
+
+    pub fn low_data_rate_optimize(mut self, on: bool) -> Self {
+        self.low_data_rate_optimize = Some(on);
+        self
+    }
+
+    pub fn sync_word(mut self, sync_word: u8) -> Self {
+        self.sync_word = Some(sync_word);
+        self
+    }
+
+    pub fn hop_period(mut self, hop_period: u8) -> Self {
+        self.hop_period = Some(hop_period);
+        self
+    }
+
+    pub fn power(mut self, power: i8) -> Self {
+        self.power = power;
+        self
+    }
+
+    pub fn tx_timeout_ms(mut self, tx_timeout_ms: u32) -> Self {
+        self.tx_timeout_ms = tx_timeout_ms;
+        self
+    }
+
+    pub fn symbol_timeout(mut self, symbol_timeout: u16) -> Self {
+        self.symbol_timeout = symbol_timeout;
+        self
+    }
+
+    pub fn continuous_rx(mut self, continuous_rx: bool) -> Self {
+        self.continuous_rx = continuous_rx;
+        self
+    }
+
+    // the rule the drivers apply when no override is given
+    pub fn default_low_data_rate_optimize(&self) -> bool {
+        match (self.bandwidth, self.spreading_factor) {
+            (LoRaBandwidth::_125KHZ, LoRaSpreadingFactor::_11)
+            | (LoRaBandwidth::_125KHZ, LoRaSpreadingFactor::_12)
+            | (LoRaBandwidth::_250KHZ, LoRaSpreadingFactor::_12) => true,
+            _ => false,
+        }
+    }
+
+    pub fn airtime(&self) -> LoRaAirtime {
+        LoRaAirtime {
+            spreading_factor: self.spreading_factor,
+            bandwidth: self.bandwidth,
+            coding_rate: self.coding_rate,
+            preamble_len: self.preamble_len,
+            implicit_header: self.implicit_header.is_some(),
+            crc_on: self.crc_on,
+            low_data_rate_optimize: self
+                .low_data_rate_optimize
+                .unwrap_or_else(|| self.default_low_data_rate_optimize()),
+        }
+    }
+
+    pub(crate) fn validate(&self, family: Family) -> Result<(), Error> {
+        if self.bandwidth == LoRaBandwidth::_RESERVED || self.implicit_header == Some(0) {
+            return Err(Error::InvalidConfig);
+        }
+        match family {
+            Family::Sx1272 | Family::Sx1276 => {
+                // SF6 has no header mode on SX127x
+                if self.spreading_factor == LoRaSpreadingFactor::_6
+                    && self.implicit_header.is_none()
+                {
+                    return Err(Error::InvalidConfig);
+                }
+                // 10 bit SymbTimeout
+                if self.symbol_timeout > 0x3FF {
+                    return Err(Error::InvalidConfig);
+                }
+            }
+            Family::Sx126x => {
+                // only settable through SetModulationParams, which the driver
+                // calls with its own rule
+                if let Some(on) = self.low_data_rate_optimize {
+                    if on != self.default_low_data_rate_optimize() {
+                        return Err(Error::InvalidConfig);
+                    }
+                }
+                if self.symbol_timeout > u8::MAX as u16 {
+                    return Err(Error::InvalidConfig);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // no register is touched beyond what SetTxConfig/SetRxConfig write
+    pub(crate) fn needs_registers(&self) -> bool {
+        self.low_data_rate_optimize.is_some() || self.sync_word.is_some()
+    }
+
+    pub(crate) fn tx_config(&self) -> TxConfig {
+        TxConfig {
+            modem: RadioModems_t_MODEM_LORA,
+            power: self.power,
+            fdev: 0, // always 0 for LoRa
+            bandwidth: self.bandwidth as u32,
+            datarate: self.spreading_factor as u32,
+            coderate: self.coding_rate as u8,
+            preamble_len: self.preamble_len,
+            fix_len: self.implicit_header.is_some(),
+            crc_on: self.crc_on,
+            freq_hop_on: self.hop_period.is_some(),
+            hop_period: self.hop_period.unwrap_or(0),
+            iq_inverted: self.iq_inverted,
+            timeout: self.tx_timeout_ms,
+        }
+    }
+
+    pub(crate) fn rx_config(&self) -> RxConfig {
+        RxConfig {
+            modem: RadioModems_t_MODEM_LORA,
+            bandwidth: self.bandwidth as u32,
+            datarate: self.spreading_factor as u32,
+            coderate: self.coding_rate as u8,
+            bandwidth_afc: 0, // FSK only
+            preamble_len: self.preamble_len,
+            symb_timeout: self.symbol_timeout,
+            fix_len: self.implicit_header.is_some(),
+            payload_len: self.implicit_header.unwrap_or(0),
+            crc_on: self.crc_on,
+            freq_hop_on: self.hop_period.is_some(),
+            hop_period: self.hop_period.unwrap_or(0),
+            iq_inverted: self.iq_inverted,
+            rx_continuous: self.continuous_rx,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FskConfig {
+    // bits per second
+    pub datarate: u32,
+    // all in Hz
+    pub fdev: u32,
+    pub bandwidth: u32,
+    pub bandwidth_afc: u32,
+    // in bytes
+    pub preamble_len: u16,
+    // payload length of fixed length packets, None for variable length
+    pub fix_len: Option<u8>,
+    pub crc_on: bool,
+    // up to 8 bytes; empty disables sync word detection
+    pub sync_word: Vec<u8, U8>,
+    pub whitening: bool,
+    // TX only
+    pub power: i8,
+    pub tx_timeout_ms: u32,
+    // RX only, in bytes; ignored in continuous mode
+    pub symbol_timeout: u16,
+    pub continuous_rx: bool,
+}
+
+impl FskConfig {
+    pub fn new(datarate: u32, fdev: u32, bandwidth: u32) -> FskConfig {
+        FskConfig {
+            datarate,
+            fdev,
+            bandwidth,
+            bandwidth_afc: bandwidth,
+            preamble_len: 5,
+            fix_len: None,
+            crc_on: true,
+            // matches the drivers' power-on default
+            sync_word: Vec::from_slice(&[0xC1, 0x94, 0xC1]).unwrap(),
+            whitening: true,
+            power: 14,
+            tx_timeout_ms: 3000,
+            symbol_timeout: 5,
+            continuous_rx: true,
+        }
+    }
+
+    // SX127x only, SX126x has no separate AFC bandwidth
+    pub fn bandwidth_afc(mut self, bandwidth_afc: u32) -> Self {
+        self.bandwidth_afc = bandwidth_afc;
+        self
+    }
+
+    pub fn preamble_len(mut self, preamble_len: u16) -> Self {
+        self.preamble_len = preamble_len;
+        self
+    }
+
+    pub fn fix_len(mut self, payload_len: u8) -> Self {
+        self.fix_len = Some(payload_len);
+        self
+    }
+
+    pub fn variable_len(mut self) -> Self {
+        self.fix_len = None;
+        self
+    }
+
+    pub fn crc_on(mut self, crc_on: bool) -> Self {
+        self.crc_on = crc_on;
+        self
+    }
+
+    pub fn sync_word(mut self, sync_word: &[u8]) -> Result<Self, Error> {
+        self.sync_word = Vec::from_slice(sync_word).map_err(|_| Error::InvalidSyncWord)?;
+        Ok(self)
+    }
+
+    pub fn whitening(mut self, whitening: bool) -> Self {
+        self.whitening = whitening;
+        self
+    }
+
+    pub fn power(mut self, power: i8) -> Self {
+        self.power = power;
+        self
+    }
+
+    pub fn tx_timeout_ms(mut self, tx_timeout_ms: u32) -> Self {
+        self.tx_timeout_ms = tx_timeout_ms;
+        self
+    }
+
+    pub fn symbol_timeout(mut self, symbol_timeout: u16) -> Self {
+        self.symbol_timeout = symbol_timeout;
+        self
+    }
+
+    pub fn continuous_rx(mut self, continuous_rx: bool) -> Self {
+        self.continuous_rx = continuous_rx;
+        self
+    }
+
+    pub fn airtime(&self) -> FskAirtime {
+        FskAirtime {
+            datarate: self.datarate,
+            preamble_len: self.preamble_len,
+            sync_word_len: self.sync_word.len() as u8,
+            fix_len: self.fix_len.is_some(),
+            crc_on: self.crc_on,
+        }
+    }
+
+    // the drivers spin forever on a bandwidth missing from their tables. For
+    // TX the SX127x drivers only store the bandwidths, and the SX126x ones
+    // take a bandwidth of 0, as configure_fsk_tx leaves it, to mean unused
+    pub(crate) fn validate(&self, family: Family, tx: bool) -> Result<(), Error> {
+        if self.fix_len == Some(0) || self.fdev > 200_000 {
+            return Err(Error::InvalidConfig);
+        }
+        match family {
+            Family::Sx1272 | Family::Sx1276 => {
+                let bandwidths = 2600..300_000;
+                let bandwidths_valid = tx
+                    || (bandwidths.contains(&self.bandwidth)
+                        && bandwidths.contains(&self.bandwidth_afc));
+                if !bandwidths_valid || !(1200..=300_000).contains(&self.datarate) {
+                    return Err(Error::InvalidConfig);
+                }
+            }
+            Family::Sx126x => {
+                let bandwidth_valid =
+                    (tx && self.bandwidth == 0) || (4800..500_000).contains(&self.bandwidth);
+                if !bandwidth_valid || !(600..=300_000).contains(&self.datarate) {
+                    return Err(Error::InvalidConfig);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn tx_config(&self) -> TxConfig {
+        TxConfig {
+            modem: RadioModems_t_MODEM_FSK,
+            power: self.power,
+            fdev: self.fdev,
+            bandwidth: self.bandwidth,
+            datarate: self.datarate,
+            coderate: 0, // LoRa only
+            preamble_len: self.preamble_len,
+            fix_len: self.fix_len.is_some(),
+            crc_on: self.crc_on,
+            freq_hop_on: false, // LoRa only
+            hop_period: 0,      // LoRa only
+            iq_inverted: false, // LoRa only
+            timeout: self.tx_timeout_ms,
+        }
+    }
+
+    pub(crate) fn rx_config(&self) -> RxConfig {
+        RxConfig {
+            modem: RadioModems_t_MODEM_FSK,
+            bandwidth: self.bandwidth,
+            datarate: self.datarate,
+            coderate: 0, // LoRa only
+            bandwidth_afc: self.bandwidth_afc,
+            preamble_len: self.preamble_len,
+            symb_timeout: self.symbol_timeout,
+            fix_len: self.fix_len.is_some(),
+            payload_len: self.fix_len.unwrap_or(0),
+            crc_on: self.crc_on,
+            freq_hop_on: false, // LoRa only
+            hop_period: 0,      // LoRa only
+            iq_inverted: false, // LoRa only
+            rx_continuous: self.continuous_rx,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RadioConfig {
+    LoRa(LoRaConfig),
+    Fsk(FskConfig),
+}
+
+impl RadioConfig {
+    // tx for the configurations given to SetTxConfig
+    pub(crate) fn validate(&self, family: Family, tx: bool) -> Result<(), Error> {
+        match self {
+            RadioConfig::LoRa(config) => config.validate(family),
+            RadioConfig::Fsk(config) => config.validate(family, tx),
+        }
+    }
+
+    pub(crate) fn modem(&self) -> RadioModems_t {
+        match self {
+            RadioConfig::LoRa(_) => RadioModems_t_MODEM_LORA,
+            RadioConfig::Fsk(_) => RadioModems_t_MODEM_FSK,
+        }
+    }
+}
+
+impl From<LoRaConfig> for RadioConfig {
+    fn from(config: LoRaConfig) -> RadioConfig {
+        RadioConfig::LoRa(config)
+    }
+}
+
+impl From<FskConfig> for RadioConfig {
+    fn from(config: FskConfig) -> RadioConfig {
+        RadioConfig::Fsk(config)
+    }
+}
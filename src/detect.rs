@@ -0,0 +1,215 @@
+// Identification and a register self-test of the chip behind a set of
+// BoardBindings, before any driver is attached to it. The drivers reset the
+// chip on init, so nothing written here outlives the probe.
+use super::{BoardBindings, Error, Radio, Sx126xPa, Sx126xProfile};
+use core::ffi::c_void;
+
+const SX127X_REG_FIFOADDRPTR: u8 = 0x0D;
+const SX127X_REG_VERSION: u8 = 0x42;
+
+const SX126X_GET_STATUS: u8 = 0xC0;
+const SX126X_WRITE_REGISTER: u8 = 0x0D;
+const SX126X_READ_REGISTER: u8 = 0x1D;
+// ASCII part name, e.g. "SX1262 V2D 2D02"
+const SX126X_REG_VERSION_STRING: u16 = 0x0320;
+// first byte of the FSK sync word
+const SX126X_REG_SYNCWORD: u16 = 0x06C0;
+// as SX126X_BUSY_TIMEOUT_MS in the drivers
+const SX126X_BUSY_TIMEOUT_MS: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Chip {
+    Sx1272,
+    // also the SX1277, SX1278 and SX1279, which report the same version
+    Sx1276,
+    Sx1261,
+    Sx1262,
+    Sx1268,
+}
+
+impl Chip {
+    // the SX126x wiring comes from profile, the PA from the chip
+    pub fn radio(self, profile: Sx126xProfile) -> Radio {
+        match self {
+            Chip::Sx1272 => Radio::sx1272(),
+            Chip::Sx1276 => Radio::sx1276(),
+            Chip::Sx1261 => Radio::sx126x(Sx126xProfile {
+                pa: Sx126xPa::Sx1261,
+                ..profile
+            }),
+            Chip::Sx1262 | Chip::Sx1268 => Radio::sx126x(Sx126xProfile {
+                pa: Sx126xPa::Sx1262,
+                ..profile
+            }),
+        }
+    }
+}
+
+struct Bus {
+    context: *mut c_void,
+    spi_in_out: unsafe extern "C" fn(*mut c_void, u8) -> u8,
+    spi_nss: unsafe extern "C" fn(*mut c_void, bool),
+    reset: unsafe extern "C" fn(*mut c_void, bool),
+    delay_ms: unsafe extern "C" fn(*mut c_void, u32),
+    busy_pin_status: Option<unsafe extern "C" fn(*mut c_void) -> bool>,
+}
+
+impl Bus {
+    fn new(bindings: &BoardBindings) -> Result<Bus, Error> {
+        match (
+            bindings.spi_in_out,
+            bindings.spi_nss,
+            bindings.reset,
+            bindings.delay_ms,
+        ) {
+            (Some(spi_in_out), Some(spi_nss), Some(reset), Some(delay_ms)) => Ok(Bus {
+                context: bindings.context,
+                spi_in_out,
+                spi_nss,
+                reset,
+                delay_ms,
+                busy_pin_status: bindings.busy_pin_status,
+            }),
+            _ => Err(Error::MissingDriverFunction),
+        }
+    }
+
+    fn transfer(&self, data: &mut [u8]) {
+        unsafe {
+            (self.spi_nss)(self.context, false);
+            for byte in data.iter_mut() {
+                *byte = (self.spi_in_out)(self.context, *byte);
+            }
+            (self.spi_nss)(self.context, true);
+        }
+    }
+
+    // long enough for either family to come out of reset
+    fn reset(&self) {
+        unsafe {
+            (self.reset)(self.context, true);
+            (self.delay_ms)(self.context, 20);
+            (self.reset)(self.context, false);
+            (self.delay_ms)(self.context, 10);
+        }
+    }
+
+    // false if BUSY stayed high
+    fn wait_on_busy(&self) -> bool {
+        let busy_pin_status = match self.busy_pin_status {
+            Some(busy_pin_status) => busy_pin_status,
+            None => return true,
+        };
+        for _ in 0..SX126X_BUSY_TIMEOUT_MS {
+            if !unsafe { busy_pin_status(self.context) } {
+                return true;
+            }
+            unsafe { (self.delay_ms)(self.context, 1) };
+        }
+        !unsafe { busy_pin_status(self.context) }
+    }
+
+    fn sx127x_read(&self, address: u8) -> u8 {
+        let mut data = [address & 0x7F, 0];
+        self.transfer(&mut data);
+        data[1]
+    }
+
+    fn sx127x_write(&self, address: u8, value: u8) {
+        self.transfer(&mut [address | 0x80, value]);
+    }
+
+    fn sx126x_read(&self, address: u16, buffer: &mut [u8]) {
+        let mut data = [0; 20];
+        data[..3].copy_from_slice(&[SX126X_READ_REGISTER, (address >> 8) as u8, address as u8]);
+        let len = 4 + buffer.len();
+        self.transfer(&mut data[..len]);
+        buffer.copy_from_slice(&data[4..len]);
+    }
+
+    fn sx126x_write(&self, address: u16, value: u8) {
+        self.transfer(&mut [
+            SX126X_WRITE_REGISTER,
+            (address >> 8) as u8,
+            address as u8,
+            value,
+        ]);
+    }
+}
+
+// the SX127x version register is read first: an SX126x ignores the unknown
+// opcode, whereas GetStatus would be a write of 0 to RegDioMapping1 on an
+// SX127x. Only SX126x boards wire BUSY, and on those a chip that fails the
+// SX127x self-test is given the SX126x probe
+pub(crate) fn probe(bindings: &BoardBindings) -> Result<Chip, Error> {
+    let bus = Bus::new(bindings)?;
+    bus.reset();
+    let ready = bus.wait_on_busy();
+    let version = bus.sx127x_read(SX127X_REG_VERSION);
+    let sx127x = match version {
+        0x12 => Some(Chip::Sx1276),
+        0x22 => Some(Chip::Sx1272),
+        _ => None,
+    };
+    if let Some(chip) = sx127x {
+        if sx127x_self_test(&bus) {
+            return Ok(chip);
+        }
+    }
+    if bus.busy_pin_status.is_some() {
+        if !(ready && bus.wait_on_busy()) {
+            return Err(Error::RadioBusyTimeout);
+        }
+        let mut status = [SX126X_GET_STATUS, 0];
+        bus.transfer(&mut status);
+        // STDBY_RC or STDBY_XOSC in bits 6:4, bit 7 reserved
+        if status[1] & 0x80 == 0 && matches!(status[1] >> 4, 0x2 | 0x3) {
+            return probe_sx126x(&bus);
+        }
+    }
+    match version {
+        _ if sx127x.is_some() => Err(Error::SelfTestFailed),
+        // MISO pulled low or floating
+        0x00 => Err(Error::NoChip),
+        // MISO shorted to the supply
+        0xFF => Err(Error::SpiShort),
+        _ => Err(Error::UnknownChip),
+    }
+}
+
+fn sx127x_self_test(bus: &Bus) -> bool {
+    [0x55, 0xAA].iter().all(|&pattern| {
+        bus.sx127x_write(SX127X_REG_FIFOADDRPTR, pattern);
+        bus.sx127x_read(SX127X_REG_FIFOADDRPTR) == pattern
+    })
+}
+
+fn probe_sx126x(bus: &Bus) -> Result<Chip, Error> {
+    let ready = || {
+        if bus.wait_on_busy() {
+            Ok(())
+        } else {
+            Err(Error::RadioBusyTimeout)
+        }
+    };
+    let mut name = [0; 6];
+    ready()?;
+    bus.sx126x_read(SX126X_REG_VERSION_STRING, &mut name);
+    let chip = match &name {
+        b"SX1261" => Chip::Sx1261,
+        b"SX1262" => Chip::Sx1262,
+        b"SX1268" => Chip::Sx1268,
+        _ => return Err(Error::UnknownChip),
+    };
+    for &pattern in &[0x55, 0xAA] {
+        let mut value = [0];
+        ready()?;
+        bus.sx126x_write(SX126X_REG_SYNCWORD, pattern);
+        ready()?;
+        bus.sx126x_read(SX126X_REG_SYNCWORD, &mut value);
+        if value[0] != pattern {
+            return Err(Error::SelfTestFailed);
+        }
+    }
+    Ok(chip)
+}
@@ -0,0 +1,189 @@
+// Regulatory duty-cycle and dwell-time limits, enforced before each send.
+//
+// Every transmission of airtime T in a sub-band limited to 1/N keeps that
+// sub-band closed until T * N after it started, as the LoRaWAN MAC does for
+// ETSI bands. Time is read from BoardBindings::get_time_ms, so the board must
+// provide a clock.
+use super::{Board, Error, Lbt, Sx12xx};
+use heapless::consts::*;
+use heapless::Vec;
+
+#[derive(Debug, Clone, Copy)]
+struct SubBand {
+    min_hz: u32,
+    max_hz: u32,
+    // the band may be used 1/duty_cycle of the time
+    duty_cycle: u16,
+    ready_at_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DutyCycle {
+    sub_bands: Vec<SubBand, U8>,
+    max_dwell_time_ms: Option<u32>,
+}
+
+impl Default for DutyCycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DutyCycle {
+    // no limits until sub-bands or a dwell time are added
+    pub fn new() -> DutyCycle {
+        DutyCycle {
+            sub_bands: Vec::new(),
+            max_dwell_time_ms: None,
+        }
+    }
+
+    // ETSI EN 300 220 sub-bands used by EU868
+    pub fn eu868() -> DutyCycle {
+        let mut duty_cycle = DutyCycle::new();
+        for &(min_hz, max_hz, duty) in &[
+            (863_000_000, 865_000_000, 1000),
+            (865_000_000, 868_000_000, 100),
+            (868_000_000, 868_600_000, 100),
+            (868_700_000, 869_200_000, 1000),
+            (869_400_000, 869_650_000, 10),
+            (869_700_000, 870_000_000, 100),
+        ] {
+            duty_cycle.add_sub_band(min_hz, max_hz, duty).unwrap();
+        }
+        duty_cycle
+    }
+
+    // 400 ms per transmission, as in AS923 and US915
+    pub fn dwell_time_400ms() -> DutyCycle {
+        let mut duty_cycle = DutyCycle::new();
+        duty_cycle.set_max_dwell_time_ms(Some(400));
+        duty_cycle
+    }
+
+    // frequencies in Hz, from min_hz up to but not including max_hz, so that
+    // adjacent sub-bands may share an edge; duty_cycle is the denominator,
+    // e.g. 100 for 1%
+    pub fn add_sub_band(&mut self, min_hz: u32, max_hz: u32, duty_cycle: u16) -> Result<(), Error> {
+        if min_hz >= max_hz || duty_cycle == 0 {
+            return Err(Error::InvalidSubBand);
+        }
+        self.sub_bands
+            .push(SubBand {
+                min_hz,
+                max_hz,
+                duty_cycle,
+                ready_at_ms: None,
+            })
+            .map_err(|_| Error::InvalidSubBand)
+    }
+
+    pub fn set_max_dwell_time_ms(&mut self, max_dwell_time_ms: Option<u32>) {
+        self.max_dwell_time_ms = max_dwell_time_ms;
+    }
+
+    fn sub_band(&mut self, frequency: u32) -> Option<&mut SubBand> {
+        self.sub_bands
+            .iter_mut()
+            .find(|band| band.min_hz <= frequency && frequency < band.max_hz)
+    }
+
+    // ms to wait before a transmission of airtime_ms may start on frequency;
+    // frequencies outside every sub-band are only subject to the dwell time
+    pub fn time_until_allowed(
+        &mut self,
+        frequency: u32,
+        airtime_ms: u32,
+        now_ms: u32,
+    ) -> Result<u32, Error> {
+        if let Some(max_dwell_time_ms) = self.max_dwell_time_ms {
+            if airtime_ms > max_dwell_time_ms {
+                return Err(Error::DwellTimeExceeded);
+            }
+        }
+        match self.sub_band(frequency).and_then(|band| band.ready_at_ms) {
+            // wrapping comparison, as the clock may roll over
+            Some(ready_at_ms) if (ready_at_ms.wrapping_sub(now_ms) as i32) > 0 => {
+                Ok(ready_at_ms.wrapping_sub(now_ms))
+            }
+            _ => Ok(0),
+        }
+    }
+
+    pub fn record(&mut self, frequency: u32, airtime_ms: u32, start_ms: u32) {
+        if let Some(band) = self.sub_band(frequency) {
+            let off_time_ms = airtime_ms.saturating_mul(band.duty_cycle as u32);
+            band.ready_at_ms = Some(start_ms.wrapping_add(off_time_ms));
+        }
+    }
+
+    // sends on the current frequency of sx12xx, or fails with
+    // Error::DutyCycleExceeded carrying the ms left to wait
+    pub(crate) fn send<B: Board>(
+        &mut self,
+        sx12xx: &mut Sx12xx<B>,
+        buffer: &mut [u8],
+        lbt: Option<Lbt>,
+    ) -> Result<(), Error> {
+        let frequency = sx12xx.get_frequency();
+        let airtime_ms = sx12xx.time_on_air(buffer.len() as u8)?;
+        let now_ms = sx12xx.now_ms().ok_or(Error::NoClock)?;
+        let wait_ms = self.time_until_allowed(frequency, airtime_ms, now_ms)?;
+        if wait_ms > 0 {
+            return Err(Error::DutyCycleExceeded(wait_ms));
+        }
+        if let Some(lbt) = lbt {
+            if !sx12xx.is_channel_free(frequency, lbt.threshold_dbm, lbt.sense_time_ms)? {
+                return Err(Error::ChannelBusy);
+            }
+        }
+        // carrier sense takes a while; the off time runs from the start of TX
+        let start_ms = sx12xx.now_ms().ok_or(Error::NoClock)?;
+        sx12xx.send(buffer);
+        sx12xx.check_busy()?;
+        self.record(frequency, airtime_ms, start_ms);
+        Ok(())
+    }
+}
+
+// an Sx12xx whose sends are held to a DutyCycle
+#[derive(Debug)]
+pub struct Regulated<B = ()> {
+    sx12xx: Sx12xx<B>,
+    duty_cycle: DutyCycle,
+}
+
+impl<B: Board> Regulated<B> {
+    pub fn new(sx12xx: Sx12xx<B>, duty_cycle: DutyCycle) -> Self {
+        Self { sx12xx, duty_cycle }
+    }
+
+    pub fn send(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        self.duty_cycle.send(&mut self.sx12xx, buffer, None)
+    }
+
+    pub fn send_lbt(&mut self, buffer: &mut [u8], lbt: Lbt) -> Result<(), Error> {
+        self.duty_cycle.send(&mut self.sx12xx, buffer, Some(lbt))
+    }
+
+    // ms until a packet of len bytes may be sent with the current configuration
+    pub fn time_until_next_tx(&mut self, len: u8) -> Result<u32, Error> {
+        let frequency = self.sx12xx.get_frequency();
+        let airtime_ms = self.sx12xx.time_on_air(len)?;
+        let now_ms = self.sx12xx.now_ms().ok_or(Error::NoClock)?;
+        self.duty_cycle
+            .time_until_allowed(frequency, airtime_ms, now_ms)
+    }
+
+    pub fn get_sx12xx(&mut self) -> &mut Sx12xx<B> {
+        &mut self.sx12xx
+    }
+
+    pub fn get_duty_cycle(&mut self) -> &mut DutyCycle {
+        &mut self.duty_cycle
+    }
+
+    pub fn into_inner(self) -> Sx12xx<B> {
+        self.sx12xx
+    }
+}
@@ -0,0 +1,789 @@
+// Register-level models of the SX1276 and SX126x behind BoardBindings, so that
+// the unmodified C drivers can be exercised on the host.
+//
+// Each emulator hands out BoardBindings whose spi_in_out/spi_nss/reset and
+// busy_pin_status callbacks act on the model. Tests drive the chip from the
+// outside (inject_rx, expire_rx) and collect the DIO edges the model raises
+// as Events to feed into Sx12xx::handle_event. Only the LoRa packet engine is
+// modelled; other registers simply hold what was written to them.
+use super::{BoardBindings, Event, PaSelect};
+use core::ffi::c_void;
+use std::boxed::Box;
+use std::sync::{Mutex, MutexGuard};
+use std::vec::Vec;
+
+const NUM_DIOS: usize = 6;
+
+fn lock<'a, M>(context: *mut c_void) -> MutexGuard<'a, M> {
+    let model = unsafe { &*(context as *const Mutex<M>) };
+    model.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// reports DIO lines that went high since the last call, one at a time
+struct Edges {
+    levels: [bool; NUM_DIOS],
+}
+
+impl Edges {
+    fn new() -> Edges {
+        Edges {
+            levels: [false; NUM_DIOS],
+        }
+    }
+
+    fn take(&mut self, levels: [bool; NUM_DIOS], timestamp: u32) -> Option<Event> {
+        let mut rising = None;
+        for dio in 0..NUM_DIOS {
+            if levels[dio] && !self.levels[dio] && rising.is_none() {
+                rising = Some(dio);
+                self.levels[dio] = true;
+            } else if !levels[dio] {
+                self.levels[dio] = false;
+            }
+        }
+        rising.map(|dio| match dio {
+            0 => Event::DIO0(timestamp),
+            1 => Event::DIO1(timestamp),
+            2 => Event::DIO2(timestamp),
+            3 => Event::DIO3(timestamp),
+            4 => Event::DIO4(timestamp),
+            _ => Event::DIO5(timestamp),
+        })
+    }
+}
+
+mod sx1276_regs {
+    pub const FIFO: u8 = 0x00;
+    pub const OPMODE: u8 = 0x01;
+    pub const FRFMSB: u8 = 0x06;
+    pub const FIFOADDRPTR: u8 = 0x0D;
+    pub const FIFOTXBASEADDR: u8 = 0x0E;
+    pub const FIFORXBASEADDR: u8 = 0x0F;
+    pub const FIFORXCURRENTADDR: u8 = 0x10;
+    pub const IRQFLAGSMASK: u8 = 0x11;
+    pub const IRQFLAGS: u8 = 0x12;
+    pub const RXNBBYTES: u8 = 0x13;
+    pub const PKTSNRVALUE: u8 = 0x19;
+    pub const PKTRSSIVALUE: u8 = 0x1A;
+    pub const PAYLOADLENGTH: u8 = 0x22;
+    pub const IRQFLAGS2: u8 = 0x3F;
+    pub const DIOMAPPING1: u8 = 0x40;
+    pub const DIOMAPPING2: u8 = 0x41;
+    pub const VERSION: u8 = 0x42;
+
+    pub const LONGRANGEMODE: u8 = 0x80;
+    pub const MODE_MASK: u8 = 0x07;
+    pub const MODE_STANDBY: u8 = 0x01;
+    pub const MODE_TX: u8 = 0x03;
+    pub const MODE_RXCONTINUOUS: u8 = 0x05;
+    pub const MODE_RXSINGLE: u8 = 0x06;
+    pub const MODE_CAD: u8 = 0x07;
+
+    pub const IRQ_RXTIMEOUT: u8 = 0x80;
+    pub const IRQ_RXDONE: u8 = 0x40;
+    pub const IRQ_VALIDHEADER: u8 = 0x10;
+    pub const IRQ_TXDONE: u8 = 0x08;
+    pub const IRQ_CADDONE: u8 = 0x04;
+    pub const IRQ_FHSSCHANGEDCHANNEL: u8 = 0x02;
+    pub const IRQ_CADDETECTED: u8 = 0x01;
+    pub const IRQ2_PACKETSENT: u8 = 0x08;
+}
+
+struct Sx1276Model {
+    regs: [u8; 0x80],
+    fifo: [u8; 256],
+    // address of the SPI transaction in progress, if any
+    address: Option<u8>,
+    write: bool,
+    writes: Vec<(u8, u8)>,
+    tx_frames: Vec<Vec<u8>>,
+    channel_activity: bool,
+    edges: Edges,
+}
+
+impl Sx1276Model {
+    fn new() -> Sx1276Model {
+        let mut model = Sx1276Model {
+            regs: [0; 0x80],
+            fifo: [0; 256],
+            address: None,
+            write: false,
+            writes: Vec::new(),
+            tx_frames: Vec::new(),
+            channel_activity: false,
+            edges: Edges::new(),
+        };
+        model.reset();
+        model
+    }
+
+    fn reset(&mut self) {
+        use sx1276_regs::*;
+        self.regs = [0; 0x80];
+        self.regs[OPMODE as usize] = 0x09;
+        self.regs[FRFMSB as usize] = 0x6C;
+        self.regs[FRFMSB as usize + 1] = 0x80;
+        self.regs[VERSION as usize] = 0x12;
+    }
+
+    fn lora(&self) -> bool {
+        self.regs[sx1276_regs::OPMODE as usize] & sx1276_regs::LONGRANGEMODE != 0
+    }
+
+    fn mode(&self) -> u8 {
+        self.regs[sx1276_regs::OPMODE as usize] & sx1276_regs::MODE_MASK
+    }
+
+    fn set_mode(&mut self, mode: u8) {
+        let opmode = &mut self.regs[sx1276_regs::OPMODE as usize];
+        *opmode = (*opmode & !sx1276_regs::MODE_MASK) | mode;
+    }
+
+    // flags masked in RegIrqFlagsMask are never raised
+    fn raise(&mut self, flags: u8) {
+        let mask = self.regs[sx1276_regs::IRQFLAGSMASK as usize];
+        self.regs[sx1276_regs::IRQFLAGS as usize] |= flags & !mask;
+    }
+
+    fn frequency(&self) -> u32 {
+        let frf = ((self.regs[0x06] as u32) << 16)
+            | ((self.regs[0x07] as u32) << 8)
+            | self.regs[0x08] as u32;
+        (frf as f64 * 61.03515625) as u32
+    }
+
+    fn spi(&mut self, data: u8) -> u8 {
+        let address = match self.address {
+            None => {
+                self.write = data & 0x80 != 0;
+                self.address = Some(data & 0x7F);
+                return 0;
+            }
+            Some(address) => address,
+        };
+        // bursts stay on the FIFO and walk through the other registers
+        if address != sx1276_regs::FIFO {
+            self.address = Some((address + 1) & 0x7F);
+        }
+        if self.write {
+            self.write_reg(address, data);
+            0
+        } else {
+            self.read_reg(address)
+        }
+    }
+
+    fn fifo_ptr(&mut self) -> &mut u8 {
+        &mut self.regs[sx1276_regs::FIFOADDRPTR as usize]
+    }
+
+    fn read_reg(&mut self, address: u8) -> u8 {
+        if address == sx1276_regs::FIFO {
+            let ptr = *self.fifo_ptr();
+            *self.fifo_ptr() = ptr.wrapping_add(1);
+            self.fifo[ptr as usize]
+        } else {
+            self.regs[address as usize]
+        }
+    }
+
+    fn write_reg(&mut self, address: u8, value: u8) {
+        use sx1276_regs::*;
+        if address == FIFO {
+            let ptr = *self.fifo_ptr();
+            *self.fifo_ptr() = ptr.wrapping_add(1);
+            self.fifo[ptr as usize] = value;
+            return;
+        }
+        self.writes.push((address, value));
+        match address {
+            IRQFLAGS if self.lora() => self.regs[IRQFLAGS as usize] &= !value,
+            OPMODE => {
+                self.regs[OPMODE as usize] = value;
+                self.regs[IRQFLAGS2 as usize] &= !IRQ2_PACKETSENT;
+                self.on_mode_change();
+            }
+            _ => self.regs[address as usize] = value,
+        }
+    }
+
+    // transmissions and channel activity detections complete at once
+    fn on_mode_change(&mut self) {
+        use sx1276_regs::*;
+        match self.mode() {
+            MODE_TX if self.lora() => {
+                let base = self.regs[FIFOTXBASEADDR as usize] as usize;
+                let len = self.regs[PAYLOADLENGTH as usize] as usize;
+                let frame = (0..len).map(|i| self.fifo[(base + i) % 256]).collect();
+                self.tx_frames.push(frame);
+                self.raise(IRQ_TXDONE);
+                self.set_mode(MODE_STANDBY);
+            }
+            MODE_TX => {
+                self.regs[IRQFLAGS2 as usize] |= IRQ2_PACKETSENT;
+            }
+            MODE_CAD if self.lora() => {
+                let detected = if self.channel_activity {
+                    IRQ_CADDETECTED
+                } else {
+                    0
+                };
+                self.raise(IRQ_CADDONE | detected);
+                self.set_mode(MODE_STANDBY);
+            }
+            _ => (),
+        }
+    }
+
+    fn receiving(&self) -> bool {
+        let mode = self.mode();
+        self.lora()
+            && (mode == sx1276_regs::MODE_RXCONTINUOUS || mode == sx1276_regs::MODE_RXSINGLE)
+    }
+
+    fn inject_rx(&mut self, data: &[u8], rssi: i16, snr: i8) -> bool {
+        use sx1276_regs::*;
+        if !self.receiving() {
+            return false;
+        }
+        let base = self.regs[FIFORXBASEADDR as usize];
+        for (i, byte) in data.iter().enumerate() {
+            self.fifo[(base as usize + i) % 256] = *byte;
+        }
+        self.regs[FIFORXCURRENTADDR as usize] = base;
+        self.regs[RXNBBYTES as usize] = data.len() as u8;
+        self.regs[PKTSNRVALUE as usize] = snr.saturating_mul(4) as u8;
+        self.regs[PKTRSSIVALUE as usize] = self.encode_rssi(rssi, snr);
+        self.raise(IRQ_RXDONE | IRQ_VALIDHEADER);
+        if self.mode() == MODE_RXSINGLE {
+            self.set_mode(MODE_STANDBY);
+        }
+        true
+    }
+
+    // inverse of the driver's RSSI computation for the current band
+    fn encode_rssi(&self, rssi: i16, snr: i8) -> u8 {
+        let offset = if self.frequency() > 525_000_000 {
+            -157
+        } else {
+            -164
+        };
+        let snr = if snr < 0 { snr as i16 } else { 0 };
+        (0..=255u8)
+            .min_by_key(|r| {
+                let r = *r as i16;
+                (offset + r + (r >> 4) + snr - rssi).abs()
+            })
+            .unwrap_or(0)
+    }
+
+    fn expire_rx(&mut self) -> bool {
+        if self.mode() != sx1276_regs::MODE_RXSINGLE || !self.lora() {
+            return false;
+        }
+        self.raise(sx1276_regs::IRQ_RXTIMEOUT);
+        self.set_mode(sx1276_regs::MODE_STANDBY);
+        true
+    }
+
+    fn dio_levels(&self) -> [bool; NUM_DIOS] {
+        use sx1276_regs::*;
+        let map1 = self.regs[DIOMAPPING1 as usize];
+        let map2 = self.regs[DIOMAPPING2 as usize];
+        let mut levels = [false; NUM_DIOS];
+        if !self.lora() {
+            // only PacketSent on DIO0 is modelled for FSK
+            levels[0] = map1 >> 6 == 0 && self.regs[IRQFLAGS2 as usize] & IRQ2_PACKETSENT != 0;
+            return levels;
+        }
+        let flags = self.regs[IRQFLAGS as usize];
+        let set = |flag: u8| flags & flag != 0;
+        levels[0] = match map1 >> 6 {
+            0 => set(IRQ_RXDONE),
+            1 => set(IRQ_TXDONE),
+            2 => set(IRQ_CADDONE),
+            _ => false,
+        };
+        levels[1] = match (map1 >> 4) & 0x03 {
+            0 => set(IRQ_RXTIMEOUT),
+            1 => set(IRQ_FHSSCHANGEDCHANNEL),
+            2 => set(IRQ_CADDETECTED),
+            _ => false,
+        };
+        levels[2] = (map1 >> 2) & 0x03 == 0 && set(IRQ_FHSSCHANGEDCHANNEL);
+        levels[3] = match map1 & 0x03 {
+            0 => set(IRQ_CADDONE),
+            1 => set(IRQ_VALIDHEADER),
+            _ => false,
+        };
+        levels[4] = map2 >> 6 == 0 && set(IRQ_CADDETECTED);
+        levels
+    }
+}
+
+pub struct Sx1276Emulator {
+    // boxed so that the bindings' context stays put when the emulator moves
+    model: Box<Mutex<Sx1276Model>>,
+}
+
+impl Sx1276Emulator {
+    pub fn new() -> Sx1276Emulator {
+        Sx1276Emulator {
+            model: Box::new(Mutex::new(Sx1276Model::new())),
+        }
+    }
+
+    fn model(&self) -> MutexGuard<'_, Sx1276Model> {
+        self.model.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // the bindings borrow the model: the emulator must outlive the Sx12xx
+    // built from them
+    pub fn bindings(&self) -> BoardBindings {
+        let context = &*self.model as *const Mutex<_> as *mut c_void;
+        BoardBindings {
+            context,
+            spi_in_out: Some(sx1276_spi_in_out),
+            spi_nss: Some(sx1276_spi_nss),
+            reset: Some(sx1276_reset),
+            delay_ms: Some(delay_ms),
+            busy_pin_status: None,
+            reduce_power: None,
+            pa_select: PaSelect::PaSelectAuto,
+            set_board_tcxo: None,
+            set_antenna_pins: None,
+            timer_start: None,
+            timer_stop: None,
+            get_time_ms: None,
+        }
+    }
+
+    pub fn register(&self, address: u8) -> u8 {
+        self.model().regs[address as usize & 0x7F]
+    }
+
+    // register writes made by the drivers since the last call, FIFO excluded
+    pub fn take_writes(&mut self) -> Vec<(u8, u8)> {
+        core::mem::take(&mut self.model().writes)
+    }
+
+    pub fn frequency(&self) -> u32 {
+        self.model().frequency()
+    }
+
+    pub fn take_tx(&mut self) -> Option<Vec<u8>> {
+        let mut model = self.model();
+        if model.tx_frames.is_empty() {
+            None
+        } else {
+            Some(model.tx_frames.remove(0))
+        }
+    }
+
+    pub fn inject_rx(&mut self, data: &[u8], rssi: i16, snr: i8) -> bool {
+        self.model().inject_rx(data, rssi, snr)
+    }
+
+    pub fn expire_rx(&mut self) -> bool {
+        self.model().expire_rx()
+    }
+
+    pub fn set_channel_activity(&mut self, detected: bool) {
+        self.model().channel_activity = detected;
+    }
+
+    // next DIO rising edge, to feed into Sx12xx::handle_event
+    pub fn take_event(&mut self, timestamp: u32) -> Option<Event> {
+        let mut model = self.model();
+        let levels = model.dio_levels();
+        model.edges.take(levels, timestamp)
+    }
+}
+
+impl Default for Sx1276Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+extern "C" fn sx1276_spi_in_out(context: *mut c_void, out_data: u8) -> u8 {
+    lock::<Sx1276Model>(context).spi(out_data)
+}
+
+extern "C" fn sx1276_spi_nss(context: *mut c_void, sel: bool) {
+    // NSS is active low; raising it ends the transaction
+    if sel {
+        lock::<Sx1276Model>(context).address = None;
+    }
+}
+
+extern "C" fn sx1276_reset(context: *mut c_void, enable: bool) {
+    if !enable {
+        lock::<Sx1276Model>(context).reset();
+    }
+}
+
+extern "C" fn delay_ms(_context: *mut c_void, _ms: u32) {}
+
+mod sx126x_cmds {
+    pub const WRITE_REGISTER: u8 = 0x0D;
+    pub const READ_REGISTER: u8 = 0x1D;
+    pub const WRITE_BUFFER: u8 = 0x0E;
+    pub const READ_BUFFER: u8 = 0x1E;
+    pub const SET_SLEEP: u8 = 0x84;
+    pub const SET_STANDBY: u8 = 0x80;
+    pub const SET_FS: u8 = 0xC1;
+    pub const SET_TX: u8 = 0x83;
+    pub const SET_RX: u8 = 0x82;
+    pub const SET_CAD: u8 = 0xC5;
+    pub const SET_PACKETTYPE: u8 = 0x8A;
+    pub const GET_PACKETTYPE: u8 = 0x11;
+    pub const SET_RFFREQUENCY: u8 = 0x86;
+    pub const SET_BUFFERBASEADDRESS: u8 = 0x8F;
+    pub const SET_PACKETPARAMS: u8 = 0x8C;
+    pub const GET_RXBUFFERSTATUS: u8 = 0x13;
+    pub const GET_PACKETSTATUS: u8 = 0x14;
+    pub const CFG_DIOIRQ: u8 = 0x08;
+    pub const GET_IRQSTATUS: u8 = 0x12;
+    pub const CLR_IRQSTATUS: u8 = 0x02;
+
+    pub const IRQ_TX_DONE: u16 = 0x0001;
+    pub const IRQ_RX_DONE: u16 = 0x0002;
+    pub const IRQ_HEADER_VALID: u16 = 0x0010;
+    pub const IRQ_CAD_DONE: u16 = 0x0080;
+    pub const IRQ_CAD_ACTIVITY_DETECTED: u16 = 0x0100;
+    pub const IRQ_RX_TX_TIMEOUT: u16 = 0x0200;
+
+    pub const REG_LR_PAYLOADLENGTH: u16 = 0x0702;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sx126xMode {
+    Sleep,
+    Standby,
+    Fs,
+    Rx { continuous: bool },
+    Tx,
+}
+
+struct Sx126xModel {
+    regs: Vec<u8>,
+    buffer: [u8; 256],
+    // bytes of the SPI transaction in progress
+    transaction: Vec<u8>,
+    commands: Vec<(u8, Vec<u8>)>,
+    mode: Sx126xMode,
+    packet_type: u8,
+    packet_params: Vec<u8>,
+    frequency: u32,
+    tx_base: u8,
+    rx_base: u8,
+    rx_len: u8,
+    packet_status: [u8; 3],
+    irq_status: u16,
+    irq_mask: u16,
+    dio1_mask: u16,
+    tx_frames: Vec<Vec<u8>>,
+    channel_activity: bool,
+    // BUSY held high, as by a disconnected or browned-out chip
+    busy_stuck: bool,
+    edges: Edges,
+}
+
+impl Sx126xModel {
+    fn new() -> Sx126xModel {
+        let mut model = Sx126xModel {
+            regs: vec![0; 0x1000],
+            buffer: [0; 256],
+            transaction: Vec::new(),
+            commands: Vec::new(),
+            mode: Sx126xMode::Standby,
+            packet_type: 0,
+            packet_params: Vec::new(),
+            frequency: 0,
+            tx_base: 0,
+            rx_base: 0,
+            rx_len: 0,
+            packet_status: [0; 3],
+            irq_status: 0,
+            irq_mask: 0,
+            dio1_mask: 0,
+            tx_frames: Vec::new(),
+            channel_activity: false,
+            busy_stuck: false,
+            edges: Edges::new(),
+        };
+        // the part name read by chip detection
+        let version = b"SX1262 V2D 2D02";
+        model.regs[0x0320..0x0320 + version.len()].copy_from_slice(version);
+        model
+    }
+
+    fn status(&self) -> u8 {
+        let chip_mode = match self.mode {
+            Sx126xMode::Sleep | Sx126xMode::Standby => 0x2,
+            Sx126xMode::Fs => 0x4,
+            Sx126xMode::Rx { .. } => 0x5,
+            Sx126xMode::Tx => 0x6,
+        };
+        chip_mode << 4
+    }
+
+    // read commands answer while the transaction is still in progress
+    fn spi(&mut self, data: u8) -> u8 {
+        use sx126x_cmds::*;
+        if self.transaction.is_empty() && self.mode == Sx126xMode::Sleep {
+            self.mode = Sx126xMode::Standby;
+        }
+        let i = self.transaction.len();
+        self.transaction.push(data);
+        let t = &self.transaction;
+        match t[0] {
+            _ if i == 0 => self.status(),
+            READ_REGISTER if i >= 4 => {
+                let address = ((t[1] as usize) << 8) | t[2] as usize;
+                self.regs[(address + i - 4) % self.regs.len()]
+            }
+            READ_BUFFER if i >= 3 => self.buffer[(t[1] as usize + i - 3) % 256],
+            GET_IRQSTATUS if i == 2 => (self.irq_status >> 8) as u8,
+            GET_IRQSTATUS if i == 3 => self.irq_status as u8,
+            GET_RXBUFFERSTATUS if i == 2 => self.rx_len,
+            GET_RXBUFFERSTATUS if i == 3 => self.rx_base,
+            GET_PACKETSTATUS if (2..=4).contains(&i) => self.packet_status[i - 2],
+            GET_PACKETTYPE if i == 2 => self.packet_type,
+            _ => self.status(),
+        }
+    }
+
+    // write commands take effect once NSS goes high
+    fn end_transaction(&mut self) {
+        use sx126x_cmds::*;
+        if self.transaction.is_empty() {
+            return;
+        }
+        let t = core::mem::take(&mut self.transaction);
+        let (opcode, params) = (t[0], &t[1..]);
+        let word = |i: usize| ((params[i] as u16) << 8) | params[i + 1] as u16;
+        match opcode {
+            WRITE_REGISTER if params.len() >= 2 => {
+                let address = word(0) as usize;
+                for (i, value) in params[2..].iter().enumerate() {
+                    let len = self.regs.len();
+                    self.regs[(address + i) % len] = *value;
+                }
+            }
+            WRITE_BUFFER if !params.is_empty() => {
+                for (i, value) in params[1..].iter().enumerate() {
+                    self.buffer[(params[0] as usize + i) % 256] = *value;
+                }
+            }
+            SET_SLEEP => self.mode = Sx126xMode::Sleep,
+            SET_STANDBY => self.mode = Sx126xMode::Standby,
+            SET_FS => self.mode = Sx126xMode::Fs,
+            SET_PACKETTYPE if !params.is_empty() => self.packet_type = params[0],
+            SET_PACKETPARAMS => self.packet_params = params.to_vec(),
+            SET_RFFREQUENCY if params.len() == 4 => {
+                let raw = ((word(0) as u64) << 16) | word(2) as u64;
+                self.frequency = ((raw * 32_000_000) >> 25) as u32;
+            }
+            SET_BUFFERBASEADDRESS if params.len() == 2 => {
+                self.tx_base = params[0];
+                self.rx_base = params[1];
+            }
+            CFG_DIOIRQ if params.len() >= 4 => {
+                self.irq_mask = word(0);
+                self.dio1_mask = word(2);
+            }
+            CLR_IRQSTATUS if params.len() == 2 => self.irq_status &= !word(0),
+            SET_TX => self.transmit(),
+            SET_RX if params.len() == 3 => {
+                let timeout = ((params[0] as u32) << 16) | word(1) as u32;
+                self.mode = Sx126xMode::Rx {
+                    continuous: timeout == 0xFF_FFFF,
+                };
+            }
+            SET_CAD => {
+                let detected = if self.channel_activity {
+                    IRQ_CAD_ACTIVITY_DETECTED
+                } else {
+                    0
+                };
+                self.raise(IRQ_CAD_DONE | detected);
+                self.mode = Sx126xMode::Standby;
+            }
+            _ => (),
+        }
+        self.commands.push((opcode, params.to_vec()));
+    }
+
+    fn raise(&mut self, irqs: u16) {
+        self.irq_status |= irqs & self.irq_mask;
+    }
+
+    // the payload length sits at a different offset for LoRa and GFSK
+    fn payload_len(&self) -> usize {
+        let index = if self.packet_type == 1 { 3 } else { 6 };
+        self.packet_params.get(index).copied().unwrap_or(0) as usize
+    }
+
+    fn transmit(&mut self) {
+        let base = self.tx_base as usize;
+        let frame = (0..self.payload_len())
+            .map(|i| self.buffer[(base + i) % 256])
+            .collect();
+        self.tx_frames.push(frame);
+        self.raise(sx126x_cmds::IRQ_TX_DONE);
+        self.mode = Sx126xMode::Standby;
+    }
+
+    fn inject_rx(&mut self, data: &[u8], rssi: i16, snr: i8) -> bool {
+        use sx126x_cmds::*;
+        let continuous = match self.mode {
+            Sx126xMode::Rx { continuous } => continuous,
+            _ => return false,
+        };
+        for (i, byte) in data.iter().enumerate() {
+            self.buffer[(self.rx_base as usize + i) % 256] = *byte;
+        }
+        self.rx_len = data.len() as u8;
+        self.regs[REG_LR_PAYLOADLENGTH as usize] = self.rx_len;
+        let rssi = (-rssi * 2).max(0).min(255) as u8;
+        self.packet_status = [rssi, snr.saturating_mul(4) as u8, rssi];
+        self.raise(IRQ_RX_DONE | IRQ_HEADER_VALID);
+        if !continuous {
+            self.mode = Sx126xMode::Standby;
+        }
+        true
+    }
+
+    fn expire_rx(&mut self) -> bool {
+        if self.mode != (Sx126xMode::Rx { continuous: false }) {
+            return false;
+        }
+        self.raise(sx126x_cmds::IRQ_RX_TX_TIMEOUT);
+        self.mode = Sx126xMode::Standby;
+        true
+    }
+
+    // every IRQ is routed to DIO1 by the driver
+    fn dio_levels(&self) -> [bool; NUM_DIOS] {
+        let mut levels = [false; NUM_DIOS];
+        levels[1] = self.irq_status & self.dio1_mask != 0;
+        levels
+    }
+}
+
+pub struct Sx126xEmulator {
+    // boxed so that the bindings' context stays put when the emulator moves
+    model: Box<Mutex<Sx126xModel>>,
+}
+
+impl Sx126xEmulator {
+    pub fn new() -> Sx126xEmulator {
+        Sx126xEmulator {
+            model: Box::new(Mutex::new(Sx126xModel::new())),
+        }
+    }
+
+    fn model(&self) -> MutexGuard<'_, Sx126xModel> {
+        self.model.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // the bindings borrow the model: the emulator must outlive the Sx12xx
+    // built from them
+    pub fn bindings(&self) -> BoardBindings {
+        let context = &*self.model as *const Mutex<_> as *mut c_void;
+        BoardBindings {
+            context,
+            spi_in_out: Some(sx126x_spi_in_out),
+            spi_nss: Some(sx126x_spi_nss),
+            reset: Some(sx126x_reset),
+            delay_ms: Some(delay_ms),
+            busy_pin_status: Some(sx126x_busy_pin_status),
+            reduce_power: None,
+            pa_select: PaSelect::PaSelectAuto,
+            set_board_tcxo: None,
+            set_antenna_pins: None,
+            timer_start: None,
+            timer_stop: None,
+            get_time_ms: None,
+        }
+    }
+
+    pub fn register(&self, address: u16) -> u8 {
+        let model = self.model();
+        model.regs[address as usize % model.regs.len()]
+    }
+
+    // opcodes and parameters sent by the drivers since the last call
+    pub fn take_commands(&mut self) -> Vec<(u8, Vec<u8>)> {
+        core::mem::take(&mut self.model().commands)
+    }
+
+    pub fn frequency(&self) -> u32 {
+        self.model().frequency
+    }
+
+    pub fn take_tx(&mut self) -> Option<Vec<u8>> {
+        let mut model = self.model();
+        if model.tx_frames.is_empty() {
+            None
+        } else {
+            Some(model.tx_frames.remove(0))
+        }
+    }
+
+    pub fn inject_rx(&mut self, data: &[u8], rssi: i16, snr: i8) -> bool {
+        self.model().inject_rx(data, rssi, snr)
+    }
+
+    pub fn expire_rx(&mut self) -> bool {
+        self.model().expire_rx()
+    }
+
+    // a reset does not clear it
+    pub fn set_busy_stuck(&mut self, stuck: bool) {
+        self.model().busy_stuck = stuck;
+    }
+
+    pub fn set_channel_activity(&mut self, detected: bool) {
+        self.model().channel_activity = detected;
+    }
+
+    // next DIO rising edge, to feed into Sx12xx::handle_event
+    pub fn take_event(&mut self, timestamp: u32) -> Option<Event> {
+        let mut model = self.model();
+        let levels = model.dio_levels();
+        model.edges.take(levels, timestamp)
+    }
+}
+
+impl Default for Sx126xEmulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+extern "C" fn sx126x_spi_in_out(context: *mut c_void, out_data: u8) -> u8 {
+    lock::<Sx126xModel>(context).spi(out_data)
+}
+
+extern "C" fn sx126x_spi_nss(context: *mut c_void, sel: bool) {
+    if sel {
+        lock::<Sx126xModel>(context).end_transaction();
+    }
+}
+
+extern "C" fn sx126x_reset(context: *mut c_void, enable: bool) {
+    if !enable {
+        let mut model = lock::<Sx126xModel>(context);
+        let busy_stuck = model.busy_stuck;
+        *model = Sx126xModel::new();
+        model.busy_stuck = busy_stuck;
+    }
+}
+
+// commands complete instantly, so the chip is only busy when told to be
+extern "C" fn sx126x_busy_pin_status(context: *mut c_void) -> bool {
+    lock::<Sx126xModel>(context).busy_stuck
+}
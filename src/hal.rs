@@ -0,0 +1,418 @@
+use super::{AntPinsMode, BoardBindings, PaSelect};
+use core::convert::Infallible;
+use core::ffi::c_void;
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi::Transfer;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+// where the C callbacks find the board state; the context pointer is
+// refreshed every time the Sx12xx is selected so that it may move freely.
+// Sealed, as the C callbacks trust whatever pointer it returns
+pub trait Board: sealed::Sealed {
+    fn context(&mut self) -> Option<*mut c_void>;
+}
+
+impl sealed::Sealed for () {}
+
+// boards built from raw BoardBindings keep whatever context they were given
+impl Board for () {
+    fn context(&mut self) -> Option<*mut c_void> {
+        None
+    }
+}
+
+pub trait AntennaSwitch {
+    fn set_mode(&mut self, mode: AntPinsMode, power: u8);
+}
+
+impl<F> AntennaSwitch for F
+where
+    F: FnMut(AntPinsMode, u8),
+{
+    fn set_mode(&mut self, mode: AntPinsMode, power: u8) {
+        self(mode, power)
+    }
+}
+
+// for boards without an RF switch to drive
+pub struct NoAntenna;
+
+impl AntennaSwitch for NoAntenna {
+    fn set_mode(&mut self, _mode: AntPinsMode, _power: u8) {}
+}
+
+// the drivers' TX/RX timeout timers and a free-running millisecond clock,
+// which duty-cycle tracking and the SX126x BUSY timeout also read. When timer
+// 0, 1 or 2 runs out, Event::Timer1, Timer2 or Timer3 must be fed into
+// handle_event
+pub trait Timers {
+    fn start(&mut self, timer_id: u8, timeout_ms: u32);
+    fn stop(&mut self, timer_id: u8);
+    fn now_ms(&mut self) -> u32;
+}
+
+// the drivers go without timeouts and clock, as with raw BoardBindings that
+// leave them out
+pub struct NoTimers;
+
+impl Timers for NoTimers {
+    fn start(&mut self, _timer_id: u8, _timeout_ms: u32) {}
+
+    fn stop(&mut self, _timer_id: u8) {}
+
+    fn now_ms(&mut self) -> u32 {
+        0
+    }
+}
+
+// a TCXO whose supply the board switches (SX127x only; SX126x power theirs
+// from DIO3, see Sx126xProfile). set_power(true) returns the milliseconds it
+// takes to settle; the drivers then switch the radio over to the TCXO input
+pub trait TcxoSupply {
+    fn set_power(&mut self, enable: bool) -> u8;
+}
+
+impl<F> TcxoSupply for F
+where
+    F: FnMut(bool) -> u8,
+{
+    fn set_power(&mut self, enable: bool) -> u8 {
+        self(enable)
+    }
+}
+
+// a TCXO powered from an output pin
+pub struct TcxoPin<P> {
+    pin: P,
+    wakeup_ms: u8,
+}
+
+impl<P: OutputPin> TcxoPin<P> {
+    pub fn new(pin: P, wakeup_ms: u8) -> Self {
+        TcxoPin { pin, wakeup_ms }
+    }
+}
+
+impl<P: OutputPin> TcxoSupply for TcxoPin<P> {
+    fn set_power(&mut self, enable: bool) -> u8 {
+        if enable {
+            self.pin.set_high().ok();
+        } else {
+            self.pin.set_low().ok();
+        }
+        self.wakeup_ms
+    }
+}
+
+// for boards with a crystal; a TCXO that is always powered still needs
+// tcxo(|_| 0) for the radio to take its input
+pub struct NoTcxoSupply;
+
+impl TcxoSupply for NoTcxoSupply {
+    fn set_power(&mut self, _enable: bool) -> u8 {
+        0
+    }
+}
+
+// boards with an external PA take their share of the requested power, in
+// dBm, and switch the PA as needed; reduce returns the dB to take off
+pub trait PowerReduction {
+    fn reduce(&mut self, power: i8) -> u8;
+}
+
+impl<F> PowerReduction for F
+where
+    F: FnMut(i8) -> u8,
+{
+    fn reduce(&mut self, power: i8) -> u8 {
+        self(power)
+    }
+}
+
+// the radio puts out all of the requested power
+pub struct NoPowerReduction;
+
+impl PowerReduction for NoPowerReduction {
+    fn reduce(&mut self, _power: i8) -> u8 {
+        0
+    }
+}
+
+// SX127x radios have no BUSY line
+pub struct NoBusy;
+
+impl InputPin for NoBusy {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+}
+
+// board wiring for Sx12xx::from_hal: the pins and bus, plus the PA output
+// (PaSelectAuto unless set), the timers, the TCXO and the power reduction
+// (none unless set)
+pub struct Hal<
+    SPI,
+    NSS,
+    RESET,
+    BUSY,
+    DELAY,
+    ANT,
+    TIM = NoTimers,
+    TCXO = NoTcxoSupply,
+    PWR = NoPowerReduction,
+> {
+    spi: SPI,
+    nss: NSS,
+    reset: RESET,
+    busy: BUSY,
+    delay: DELAY,
+    antenna: ANT,
+    pa_select: PaSelect,
+    timers: Option<TIM>,
+    tcxo: Option<TCXO>,
+    power_reduction: Option<PWR>,
+}
+
+impl<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR> core::fmt::Debug
+    for Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Hal")
+    }
+}
+
+impl<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR> sealed::Sealed
+    for Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>
+{
+}
+
+impl<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR> Board
+    for Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>
+{
+    fn context(&mut self) -> Option<*mut c_void> {
+        Some(self as *mut Self as *mut c_void)
+    }
+}
+
+impl<SPI, NSS, RESET, BUSY, DELAY, ANT> Hal<SPI, NSS, RESET, BUSY, DELAY, ANT>
+where
+    SPI: Transfer<u8>,
+    NSS: OutputPin,
+    RESET: OutputPin,
+    BUSY: InputPin,
+    DELAY: DelayMs<u32>,
+    ANT: AntennaSwitch,
+{
+    // busy is only wired on SX126x; pass NoBusy for SX127x
+    pub fn new(spi: SPI, nss: NSS, reset: RESET, busy: BUSY, delay: DELAY, antenna: ANT) -> Self {
+        Self {
+            spi,
+            nss,
+            reset,
+            busy,
+            delay,
+            antenna,
+            pa_select: PaSelect::PaSelectAuto,
+            timers: None,
+            tcxo: None,
+            power_reduction: None,
+        }
+    }
+}
+
+// the C drivers cannot act on bus or pin errors, so the trampolines below
+// carry on with a neutral value instead
+impl<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>
+    Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>
+where
+    SPI: Transfer<u8>,
+    NSS: OutputPin,
+    RESET: OutputPin,
+    BUSY: InputPin,
+    DELAY: DelayMs<u32>,
+    ANT: AntennaSwitch,
+    TIM: Timers,
+    TCXO: TcxoSupply,
+    PWR: PowerReduction,
+{
+    // which PA output the board has wired up (SX127x only)
+    pub fn pa_select(mut self, pa_select: PaSelect) -> Self {
+        self.pa_select = pa_select;
+        self
+    }
+
+    pub fn timers<T: Timers>(
+        self,
+        timers: T,
+    ) -> Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, T, TCXO, PWR> {
+        Hal {
+            spi: self.spi,
+            nss: self.nss,
+            reset: self.reset,
+            busy: self.busy,
+            delay: self.delay,
+            antenna: self.antenna,
+            pa_select: self.pa_select,
+            timers: Some(timers),
+            tcxo: self.tcxo,
+            power_reduction: self.power_reduction,
+        }
+    }
+
+    pub fn tcxo<T: TcxoSupply>(
+        self,
+        tcxo: T,
+    ) -> Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, T, PWR> {
+        Hal {
+            spi: self.spi,
+            nss: self.nss,
+            reset: self.reset,
+            busy: self.busy,
+            delay: self.delay,
+            antenna: self.antenna,
+            pa_select: self.pa_select,
+            timers: self.timers,
+            tcxo: Some(tcxo),
+            power_reduction: self.power_reduction,
+        }
+    }
+
+    pub fn power_reduction<P: PowerReduction>(
+        self,
+        power_reduction: P,
+    ) -> Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, P> {
+        Hal {
+            spi: self.spi,
+            nss: self.nss,
+            reset: self.reset,
+            busy: self.busy,
+            delay: self.delay,
+            antenna: self.antenna,
+            pa_select: self.pa_select,
+            timers: self.timers,
+            tcxo: self.tcxo,
+            power_reduction: Some(power_reduction),
+        }
+    }
+
+    // the context is filled in once the Hal has settled inside its Sx12xx
+    pub(crate) fn bindings(&self) -> BoardBindings {
+        let mut bindings = BoardBindings {
+            context: core::ptr::null_mut(),
+            spi_in_out: Some(Self::spi_in_out),
+            spi_nss: Some(Self::spi_nss),
+            reset: Some(Self::reset),
+            delay_ms: Some(Self::delay_ms),
+            busy_pin_status: Some(Self::busy_pin_status),
+            reduce_power: Some(Self::reduce_power),
+            pa_select: self.pa_select,
+            set_board_tcxo: Some(Self::set_board_tcxo),
+            set_antenna_pins: Some(Self::set_antenna_pins),
+            timer_start: Some(Self::timer_start),
+            timer_stop: Some(Self::timer_stop),
+            get_time_ms: Some(Self::get_time_ms),
+        };
+        // the drivers check for a clock before relying on one
+        if self.timers.is_none() {
+            bindings.timer_start = None;
+            bindings.timer_stop = None;
+            bindings.get_time_ms = None;
+        }
+        // without set_board_tcxo the drivers stay on the crystal input
+        if self.tcxo.is_none() {
+            bindings.set_board_tcxo = None;
+        }
+        if self.power_reduction.is_none() {
+            bindings.reduce_power = None;
+        }
+        bindings
+    }
+
+    unsafe fn from_context<'a>(context: *mut c_void) -> &'a mut Self {
+        &mut *(context as *mut Self)
+    }
+
+    extern "C" fn spi_in_out(context: *mut c_void, out_data: u8) -> u8 {
+        let hal = unsafe { Self::from_context(context) };
+        let mut buf = [out_data];
+        match hal.spi.transfer(&mut buf) {
+            Ok(read) => read[0],
+            Err(_) => 0,
+        }
+    }
+
+    extern "C" fn spi_nss(context: *mut c_void, value: bool) {
+        let hal = unsafe { Self::from_context(context) };
+        if value {
+            hal.nss.set_high().ok();
+        } else {
+            hal.nss.set_low().ok();
+        }
+    }
+
+    // reset is active low
+    extern "C" fn reset(context: *mut c_void, enable: bool) {
+        let hal = unsafe { Self::from_context(context) };
+        if enable {
+            hal.reset.set_low().ok();
+        } else {
+            hal.reset.set_high().ok();
+        }
+    }
+
+    extern "C" fn delay_ms(context: *mut c_void, ms: u32) {
+        let hal = unsafe { Self::from_context(context) };
+        hal.delay.delay_ms(ms);
+    }
+
+    extern "C" fn busy_pin_status(context: *mut c_void) -> bool {
+        let hal = unsafe { Self::from_context(context) };
+        hal.busy.is_high().unwrap_or(false)
+    }
+
+    extern "C" fn reduce_power(context: *mut c_void, power: i8) -> u8 {
+        let hal = unsafe { Self::from_context(context) };
+        hal.power_reduction
+            .as_mut()
+            .map_or(0, |power_reduction| power_reduction.reduce(power))
+    }
+
+    extern "C" fn set_board_tcxo(context: *mut c_void, enable: bool) -> u8 {
+        let hal = unsafe { Self::from_context(context) };
+        hal.tcxo.as_mut().map_or(0, |tcxo| tcxo.set_power(enable))
+    }
+
+    extern "C" fn set_antenna_pins(context: *mut c_void, mode: AntPinsMode, power: u8) {
+        let hal = unsafe { Self::from_context(context) };
+        hal.antenna.set_mode(mode, power);
+    }
+
+    extern "C" fn timer_start(context: *mut c_void, timer_id: u8, timeout_ms: u32) {
+        let hal = unsafe { Self::from_context(context) };
+        if let Some(timers) = hal.timers.as_mut() {
+            timers.start(timer_id, timeout_ms);
+        }
+    }
+
+    extern "C" fn timer_stop(context: *mut c_void, timer_id: u8) {
+        let hal = unsafe { Self::from_context(context) };
+        if let Some(timers) = hal.timers.as_mut() {
+            timers.stop(timer_id);
+        }
+    }
+
+    extern "C" fn get_time_ms(context: *mut c_void) -> u32 {
+        let hal = unsafe { Self::from_context(context) };
+        hal.timers.as_mut().map_or(0, |timers| timers.now_ms())
+    }
+}
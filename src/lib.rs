@@ -1,28 +1,65 @@
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "mock")), no_std)]
 use sx12xx_sys::*;
 
 extern crate libm;
 
 use as_slice::AsMutSlice;
 use lorawan_device::radio::RxQuality;
+pub use sx12xx_sys::regs;
+use sx12xx_sys::regs::{Field, FieldValue, Register};
 pub use sx12xx_sys::AntPinsMode_t as AntPinsMode;
 pub use sx12xx_sys::BoardBindings_t as BoardBindings;
+pub use sx12xx_sys::PaSelect_t as PaSelect;
 use sx12xx_sys::Sx12xxEvent_t;
 pub use sx12xx_sys::Sx12xxRxMetadata_t as RxMetadata;
 use sx12xx_sys::Sx12xxState_t as Sx12xxState;
+mod airtime;
+mod config;
+mod detect;
+mod duty_cycle;
+#[cfg(feature = "mock")]
+pub mod emulator;
+mod hal;
 mod lorawan;
-use sx12xx_sys::sx12xx_get_raw_buffer;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod sx126x;
+use sx12xx_sys::{
+    sx12xx_get_cad_activity_detected, sx12xx_get_fhss_channel, sx12xx_get_raw_buffer,
+    sx12xx_select, sx12xx_set_rx_buffer, sx12xx_take_rx_buffer,
+};
 
+pub use airtime::{FskAirtime, LoRaAirtime};
+pub use config::{FskConfig, LoRaConfig, RadioConfig};
+pub use detect::Chip;
+pub use duty_cycle::{DutyCycle, Regulated};
+pub use hal::{
+    AntennaSwitch, Board, Hal, NoAntenna, NoBusy, NoPowerReduction, NoTcxoSupply, NoTimers,
+    PowerReduction, TcxoPin, TcxoSupply, Timers,
+};
 pub use lorawan::LorawanRadio;
+pub use sx126x::{Regulator, Sx126xPa, Sx126xProfile, Tcxo, TcxoVoltage};
+
+#[derive(Debug)]
+pub enum PacketQuality {
+    LoRa(RxQuality),
+    // FSK demodulation gives no SNR
+    Fsk { rssi: i16 },
+}
 
 #[derive(Debug)]
 pub enum Response {
     Busy,
     TxDone(u32),
-    RxDone(u32, RxQuality),
+    RxDone(u32, PacketQuality),
     TxTimeout,
     RxTimeout,
     RxError,
+    CadDone { activity_detected: bool },
+    // index into the hop table of the channel now in use
+    FhssChannelChanged(u8),
+    // a continuous wave or infinite preamble test ran for its full duration
+    TestModeDone,
 }
 
 use heapless::consts::*;
@@ -30,6 +67,32 @@ use heapless::Vec;
 
 pub struct Radio {
     c_handle: Radio_t,
+    family: Family,
+    sx126x_profile: Option<Sx126xProfile>,
+}
+
+// what configurations are valid depends on the chip family
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Family {
+    Sx1272,
+    Sx1276,
+    Sx126x,
+}
+
+// the drivers keep their state in file-scope statics (SX1276, SX126x,
+// RadioIsActive, TcxoIsOn...), one set per family, so only one Sx12xx of each
+// may be alive. The mock counts as an SX1276. thumbv6m has no compare-and-swap,
+// so claiming is not atomic: radios must not be constructed concurrently
+static FAMILY_IN_USE: [AtomicBool; 3] = [
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+];
+
+impl Family {
+    fn in_use(self) -> &'static AtomicBool {
+        &FAMILY_IN_USE[self as usize]
+    }
 }
 
 impl core::fmt::Debug for Radio {
@@ -38,6 +101,22 @@ impl core::fmt::Debug for Radio {
     }
 }
 
+// per-instance state of the C library; the drivers only hold on to it while
+// selected, so it may move along with Sx12xx
+struct Handle(Sx12xx_t);
+
+// the raw pointers in Sx12xx_t point at the drivers' statics, at the board
+// (set again by every select) and at the client's rx buffer, none of which
+// is tied to the context that built the radio. The callbacks in
+// BoardBindings must be callable from wherever the radio is used
+unsafe impl Send for Handle {}
+
+impl core::fmt::Debug for Handle {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Handle")
+    }
+}
+
 #[derive(Debug)]
 enum HopPeriod {
     Disabled,
@@ -50,35 +129,154 @@ struct IqInverted {
     tx: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Modem {
+    LoRa,
+    Fsk,
+}
+
+#[derive(Debug)]
+struct FskSettings {
+    sync_word: Vec<u8, U8>,
+    whitening: bool,
+}
+
 #[derive(Debug)]
 struct Settings {
     iq_inverted: IqInverted,
     hop_period: HopPeriod,
+    hop_table: Vec<u32, U64>,
     crc_on: bool,
     fix_len: bool,
+    payload_len: u8, // only used if fix_len
     preamble_len: u16,
     timeout: u16, // timeout in FSK bytes or LoRa symboles,
     continuous_rx: bool,
+    fsk: FskSettings,
+    rx_modem: Modem,
+    frequency: u32,
+    // last configurations handed to the driver, applied again after random_u32
+    tx_config: Option<RadioConfig>,
+    rx_config: Option<RadioConfig>,
+    rx_configured_last: bool,
+    // the drivers hold the single-mode variant of rx_config set_rx_single
+    // started with
+    rx_single_written: bool,
 }
 
+#[derive(Debug, Clone, Copy)]
+struct TxConfig {
+    modem: RadioModems_t,
+    power: i8,
+    fdev: u32,
+    bandwidth: u32,
+    datarate: u32,
+    coderate: u8,
+    preamble_len: u16,
+    fix_len: bool,
+    crc_on: bool,
+    freq_hop_on: bool,
+    hop_period: u8,
+    iq_inverted: bool,
+    timeout: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RxConfig {
+    modem: RadioModems_t,
+    bandwidth: u32,
+    datarate: u32,
+    coderate: u8,
+    bandwidth_afc: u32,
+    preamble_len: u16,
+    symb_timeout: u16,
+    fix_len: bool,
+    payload_len: u8,
+    crc_on: bool,
+    freq_hop_on: bool,
+    hop_period: u8,
+    iq_inverted: bool,
+    rx_continuous: bool,
+}
+
+// where received frames end up, see set_rx_buffer and lend_rx
 #[derive(Debug)]
-pub struct Sx12xx {
+enum RxStorage {
+    // copied into rx_buffer, as LorawanRadio expects
+    Copy,
+    // left in the driver's own buffer
+    Lend,
+    // copied by the C library into a buffer of the client's
+    User(*mut u8, usize),
+}
+
+// the buffer came from a &'static mut that Sx12xx now holds on to alone
+unsafe impl Send for RxStorage {}
+
+// B holds the board state behind the C callbacks when built with from_hal.
+//
+// Only one Sx12xx per chip family may exist at a time; constructors fail with
+// Error::RadioInUse otherwise. Sx12xx is Send, so it may be handed to the
+// task or interrupt handler that owns it. The C library still acts on the
+// global handle of whichever instance was selected last, and nothing guards
+// selection against preemption: when there are several instances, none may
+// be used from a context that preempts another's use.
+//
+// As an RngCore, next_u32, next_u64 and fill_bytes panic when the radio gives
+// no noise: the driver has no Random or the SX126x BUSY timed out. Use
+// try_fill_bytes or random_u32 where that must be handled.
+#[derive(Debug)]
+pub struct Sx12xx<B = ()> {
     settings: Settings,
     radio: Radio,
+    handle: Handle,
+    board: B,
     // changing the size of the Vec here
     // breaks many "safe" assumptions later
     rx_buffer: Vec<u8, U256>,
+    rx_storage: RxStorage,
+    // length of the last frame received
+    rx_len: usize,
+    // a continuous wave or infinite preamble test is on air
+    test_mode: bool,
+    // the reception ends with the first frame, error or timeout
+    rx_single: bool,
 }
 
 impl Radio {
+    // an SX1262 wired as on the SX1262MBXCAS shield; see sx126x for others
     pub fn sx1262() -> Radio {
+        Radio::sx126x(Sx126xProfile::default())
+    }
+    pub fn sx126x(profile: Sx126xProfile) -> Radio {
         Radio {
             c_handle: unsafe { SX126xRadioNew() },
+            family: Family::Sx126x,
+            sx126x_profile: Some(profile),
+        }
+    }
+    pub fn sx1272() -> Radio {
+        Radio {
+            c_handle: unsafe { SX1272RadioNew() },
+            family: Family::Sx1272,
+            sx126x_profile: None,
         }
     }
     pub fn sx1276() -> Radio {
         Radio {
             c_handle: unsafe { SX1276RadioNew() },
+            family: Family::Sx1276,
+            sx126x_profile: None,
+        }
+    }
+    // simulated radio driven through mock::Mock
+    #[cfg(feature = "mock")]
+    pub fn mock() -> Radio {
+        Radio {
+            c_handle: mock::radio(),
+            // validated like the chip it stands in for
+            family: Family::Sx1276,
+            sx126x_profile: None,
         }
     }
 }
@@ -87,8 +285,61 @@ impl Radio {
 pub enum Error {
     NoRadioPointer,
     UnexpectedPhyEvent,
+    InvalidSyncWord,
+    InvalidHopTable,
+    // a TxDone or RxDone was reported for an event that carries no timestamp
+    UnexpectedEventSource,
+    // the received packet does not fit the receive buffer
+    RxBufferOverflow,
+    // the driver has no handler for the event (e.g. DIO5 on SX127x) or
+    // leaves the Radio_t slot empty
+    MissingDriverFunction,
+    // listen-before-talk found the channel in use
+    ChannelBusy,
+    // carrier sense needs the radio idle (asleep or in standby)
+    RadioNotIdle,
+    // another Sx12xx of the same chip family is still alive
+    RadioInUse,
+    // the sub-band is closed for this many more ms
+    DutyCycleExceeded(u32),
+    // the packet would be on air longer than the dwell time allows
+    DwellTimeExceeded,
+    InvalidSubBand,
+    // duty-cycle tracking needs BoardBindings::get_time_ms
+    NoClock,
+    // a LoRaConfig or FskConfig the chip cannot run
+    InvalidConfig,
+    // set_rx_single reapplies the RX configuration, so one must be applied
+    NoRxConfig,
+    // reported by LorawanRadio, which goes back to Idle: the transmission
+    // did not complete, or a frame was received with a bad CRC or header
+    TxTimeout,
+    RxError,
+    // reported by LorawanRadio for a frame received with an FSK configuration
+    NotLoRa,
+    // the chip has no such mode, e.g. boosted or duty-cycled RX on SX127x
+    Unsupported,
+    // the SX126x kept its BUSY line high, so commands are dropped until
+    // Sx12xx::recover succeeds
+    RadioBusyTimeout,
+    // chip detection read only zeros (empty socket) or only ones (MISO
+    // shorted high), or a version it does not know
+    NoChip,
+    SpiShort,
+    UnknownChip,
+    // a register did not read back what was written to it
+    SelfTestFailed,
+}
+
+// listen-before-talk parameters: the channel counts as free if the RSSI stays
+// at or below threshold_dbm for sense_time_ms
+#[derive(Debug, Clone, Copy)]
+pub struct Lbt {
+    pub threshold_dbm: i16,
+    pub sense_time_ms: u32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LoRaBandwidth {
     _125KHZ = 0,
     _250KHZ = 1,
@@ -96,7 +347,10 @@ pub enum LoRaBandwidth {
     _RESERVED = 3,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LoRaSpreadingFactor {
+    // implicit header only on SX127x
+    _6 = 6,
     _7 = 7,
     _8 = 8,
     _9 = 9,
@@ -105,6 +359,7 @@ pub enum LoRaSpreadingFactor {
     _12 = 12,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LoRaCodingRate {
     _4_5 = 1,
     _4_6 = 2,
@@ -120,6 +375,7 @@ pub enum Event {
     DIO3(u32),
     DIO4(u32),
     DIO5(u32),
+    // expiry of the timers started through BoardBindings::timer_start (timer_id 0, 1, 2)
     Timer1,
     Timer2,
     Timer3,
@@ -140,72 +396,319 @@ impl From<Event> for Sx12xxEvent_t {
         }
     }
 }
+impl Event {
+    // TxDone and RxDone come from DIO0 on SX127x and from DIO1 on SX126x
+    fn dio_timestamp(&self) -> Result<u32, Error> {
+        match self {
+            Event::DIO0(t) | Event::DIO1(t) => Ok(*t),
+            _ => Err(Error::UnexpectedEventSource),
+        }
+    }
+}
+
+use core::num::NonZeroU32;
 use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi::Transfer;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use rand_core::RngCore;
 
 impl Sx12xx {
-    pub fn new(mut radio: Radio, bindings: BoardBindings) -> Sx12xx {
-        unsafe {
-            sx12xx_init(&mut radio.c_handle, bindings);
-        };
+    // one SX1276 and one SX1262 may coexist, but not two SX1276
+    pub fn new(radio: Radio, bindings: BoardBindings) -> Result<Sx12xx, Error> {
+        Sx12xx::with_board(radio, bindings, ())
+    }
+
+    // as new, for whichever chip answers; profile is only used if it is an
+    // SX126x
+    pub fn detect(
+        bindings: BoardBindings,
+        profile: Sx126xProfile,
+    ) -> Result<(Sx12xx, Chip), Error> {
+        Sx12xx::detect_with_board(bindings, (), profile)
+    }
+}
+
+impl<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>
+    Sx12xx<Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>>
+where
+    SPI: Transfer<u8>,
+    NSS: OutputPin,
+    RESET: OutputPin,
+    BUSY: InputPin,
+    DELAY: DelayMs<u32>,
+    ANT: AntennaSwitch,
+    TIM: Timers,
+    TCXO: TcxoSupply,
+    PWR: PowerReduction,
+{
+    pub fn from_hal(
+        radio: Radio,
+        hal: Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>,
+    ) -> Result<Self, Error> {
+        let bindings = hal.bindings();
+        Sx12xx::with_board(radio, bindings, hal)
+    }
 
-        Sx12xx {
+    // as from_hal, for whichever chip answers; profile is only used if it
+    // is an SX126x
+    pub fn detect_from_hal(
+        hal: Hal<SPI, NSS, RESET, BUSY, DELAY, ANT, TIM, TCXO, PWR>,
+        profile: Sx126xProfile,
+    ) -> Result<(Self, Chip), Error> {
+        let bindings = hal.bindings();
+        Sx12xx::detect_with_board(bindings, hal, profile)
+    }
+}
+
+impl<B: Board> Sx12xx<B> {
+    fn detect_with_board(
+        mut bindings: BoardBindings,
+        mut board: B,
+        profile: Sx126xProfile,
+    ) -> Result<(Sx12xx<B>, Chip), Error> {
+        // the board stays put for the duration of the probe
+        if let Some(context) = board.context() {
+            bindings.context = context;
+        }
+        let chip = detect::probe(&bindings)?;
+        Ok((
+            Sx12xx::with_board(chip.radio(profile), bindings, board)?,
+            chip,
+        ))
+    }
+
+    fn with_board(radio: Radio, mut bindings: BoardBindings, board: B) -> Result<Sx12xx<B>, Error> {
+        let in_use = radio.family.in_use();
+        if in_use.load(Ordering::Acquire) {
+            return Err(Error::RadioInUse);
+        }
+        in_use.store(true, Ordering::Release);
+        let mut sx12xx = Sx12xx {
             radio,
+            handle: Handle(unsafe { sx12xx_new_handle() }),
+            board,
             settings: Settings {
                 iq_inverted: IqInverted {
                     tx: false,
                     rx: true,
                 },
                 hop_period: HopPeriod::Disabled,
+                hop_table: Vec::new(),
                 crc_on: true,
                 fix_len: false,
+                payload_len: 0,
                 preamble_len: 8,
                 timeout: 5,
                 continuous_rx: true,
+                fsk: FskSettings {
+                    // matches the drivers' power-on default
+                    sync_word: Vec::from_slice(&[0xC1, 0x94, 0xC1]).unwrap(),
+                    whitening: true,
+                },
+                rx_modem: Modem::LoRa,
+                frequency: 0,
+                tx_config: None,
+                rx_config: None,
+                rx_configured_last: false,
+                rx_single_written: false,
             },
             rx_buffer: Vec::new(),
+            rx_storage: RxStorage::Copy,
+            rx_len: 0,
+            test_mode: false,
+            rx_single: false,
+        };
+
+        // the board is in its final place for the duration of the init
+        if let Some(context) = sx12xx.board.context() {
+            bindings.context = context;
         }
+        if let Some(profile) = sx12xx.radio.sx126x_profile {
+            sx12xx.handle.0.sx126x_profile = profile.into();
+        }
+        unsafe {
+            sx12xx_init(&mut sx12xx.handle.0, &mut sx12xx.radio.c_handle, bindings);
+        };
+        Ok(sx12xx)
     }
 
-    pub fn handle_event(&mut self, event: Event) -> Response {
-        let sx12xx_state = unsafe { sx12xx_handle_event(event.clone().into()) };
-        match sx12xx_state {
+    pub fn handle_event(&mut self, event: Event) -> Result<Response, Error> {
+        self.check_busy()?;
+        self.select();
+        let sx12xx_state = unsafe { sx12xx_handle_event(&mut self.handle.0, event.clone().into()) };
+        // the IRQ status could not be read
+        self.check_busy()?;
+        let rx_ended = matches!(
+            sx12xx_state,
+            Sx12xxState::Sx12xxState_RxDone
+                | Sx12xxState::Sx12xxState_RxTimeout
+                | Sx12xxState::Sx12xxState_RxError
+                | Sx12xxState::Sx12xxState_RxOverflow
+        );
+        let response = self.response(sx12xx_state, event);
+        if self.rx_single && rx_ended {
+            // the drivers keep receiving after a timer timeout or in
+            // continuous mode
+            self.rx_single = false;
+            self.standby();
+        }
+        response
+    }
+
+    fn response(&mut self, sx12xx_state: Sx12xxState, event: Event) -> Result<Response, Error> {
+        Ok(match sx12xx_state {
             Sx12xxState::Sx12xxState_Busy => Response::Busy,
-            Sx12xxState::Sx12xxState_TxDone => {
-                if let Event::DIO0(t) = event {
-                    Response::TxDone(t)
-                } else {
-                    panic!("TxDone assumed to follow DIO0");
-                }
-            }
+            Sx12xxState::Sx12xxState_TxDone => Response::TxDone(event.dio_timestamp()?),
             Sx12xxState::Sx12xxState_RxDone => {
-                if let Event::DIO0(t) = event {
-                    let metadata = unsafe { sx12xx_get_rx_metadata() };
-                    self.rx_buffer.resize(metadata.rx_len as usize, 0).unwrap();
+                let t = event.dio_timestamp()?;
+                let metadata = unsafe { sx12xx_get_rx_metadata(&mut self.handle.0) };
+                if let RxStorage::Copy = self.rx_storage {
+                    self.rx_buffer
+                        .resize(metadata.rx_len as usize, 0)
+                        .map_err(|_| Error::RxBufferOverflow)?;
 
                     unsafe {
-                        let src = sx12xx_get_raw_buffer();
+                        let src = sx12xx_get_raw_buffer(&mut self.handle.0);
                         ptr::copy(
                             src,
                             self.rx_buffer.as_mut_slice().as_mut_ptr(),
                             metadata.rx_len.into(),
                         );
                     }
-                    Response::RxDone(t, RxQuality::new(metadata.rssi, metadata.snr))
-                } else {
-                    panic!("TxDone assumed to follow DIO0");
                 }
+                self.rx_len = metadata.rx_len as usize;
+                let quality = match self.settings.rx_modem {
+                    Modem::LoRa => PacketQuality::LoRa(RxQuality::new(metadata.rssi, metadata.snr)),
+                    Modem::Fsk => PacketQuality::Fsk {
+                        rssi: metadata.rssi,
+                    },
+                };
+                Response::RxDone(t, quality)
+            }
+            // test modes end through the TX timeout timer
+            Sx12xxState::Sx12xxState_TxTimeout if self.test_mode => {
+                self.end_test_mode();
+                Response::TestModeDone
             }
             Sx12xxState::Sx12xxState_TxTimeout => Response::TxTimeout,
             Sx12xxState::Sx12xxState_RxTimeout => Response::RxTimeout,
             Sx12xxState::Sx12xxState_RxError => Response::RxError,
+            Sx12xxState::Sx12xxState_CadDone => Response::CadDone {
+                activity_detected: unsafe { sx12xx_get_cad_activity_detected(&mut self.handle.0) },
+            },
+            Sx12xxState::Sx12xxState_FhssChangeChannel => {
+                let channel = unsafe { sx12xx_get_fhss_channel(&mut self.handle.0) } as usize;
+                let len = self.settings.hop_table.len();
+                if len == 0 {
+                    Response::FhssChannelChanged(channel as u8)
+                } else {
+                    let idx = channel % len;
+                    let frequency = self.settings.hop_table[idx];
+                    self.set_frequency(frequency);
+                    Response::FhssChannelChanged(idx as u8)
+                }
+            }
+            Sx12xxState::Sx12xxState_RxOverflow => return Err(Error::RxBufferOverflow),
+            Sx12xxState::Sx12xxState_MissingIrqHandler => return Err(Error::MissingDriverFunction),
+        })
+    }
+
+    // Err(Error::RadioBusyTimeout) once the drivers have given up on a
+    // command, including one sent by a method that returns nothing
+    pub fn check_busy(&self) -> Result<(), Error> {
+        if self.handle.0.busy_timeout {
+            Err(Error::RadioBusyTimeout)
+        } else {
+            Ok(())
+        }
+    }
+
+    // resets the chip and applies the frequency and configurations again;
+    // the radio is left asleep, so any reception has to be restarted
+    pub fn recover(&mut self) -> Result<(), Error> {
+        self.select();
+        if !unsafe { sx12xx_recover(&mut self.handle.0, &mut self.radio.c_handle) } {
+            return Err(Error::RadioBusyTimeout);
         }
+        self.rx_single = false;
+        self.test_mode = false;
+        if self.settings.frequency != 0 {
+            self.set_frequency(self.settings.frequency);
+        }
+        self.restore_config();
+        self.sleep();
+        self.check_busy()
+    }
+
+    // the C drivers act on whichever handle was selected last
+    fn select(&mut self) {
+        if let Some(context) = self.board.context() {
+            self.handle.0.bindings.context = context;
+        }
+        unsafe { sx12xx_select(&mut self.handle.0) };
     }
 
     pub fn get_rx(&mut self) -> &mut Vec<u8, U256> {
         &mut self.rx_buffer
     }
 
+    // the last frame received, wherever it is stored; with lend_rx it is only
+    // valid until the radio is used again
+    pub fn received(&self) -> &[u8] {
+        match self.rx_storage {
+            RxStorage::Copy => &self.rx_buffer,
+            RxStorage::Lend => {
+                let raw_buffer = self.handle.0.raw_buffer;
+                if raw_buffer.is_null() {
+                    &[]
+                } else {
+                    unsafe { core::slice::from_raw_parts(raw_buffer, self.rx_len) }
+                }
+            }
+            RxStorage::User(buffer, _) => unsafe {
+                core::slice::from_raw_parts(buffer, self.rx_len)
+            },
+        }
+    }
+
+    // frames are copied by the C library straight into buffer; those longer
+    // than the buffer (or 255 bytes) fail handle_event with
+    // Error::RxBufferOverflow. Returns the buffer set before, if any
+    pub fn set_rx_buffer(&mut self, buffer: &'static mut [u8]) -> Option<&'static mut [u8]> {
+        let previous = self.take_rx_buffer();
+        let len = buffer.len().min(u8::MAX as usize) as u8;
+        unsafe { sx12xx_set_rx_buffer(&mut self.handle.0, buffer.as_mut_ptr(), len) };
+        self.rx_storage = RxStorage::User(buffer.as_mut_ptr(), buffer.len());
+        previous
+    }
+
+    // hands back the buffer given to set_rx_buffer; frames are copied into
+    // get_rx again from then on
+    pub fn take_rx_buffer(&mut self) -> Option<&'static mut [u8]> {
+        let previous = match self.rx_storage {
+            RxStorage::User(buffer, len) => unsafe {
+                sx12xx_take_rx_buffer(&mut self.handle.0);
+                Some(core::slice::from_raw_parts_mut(buffer, len))
+            },
+            _ => None,
+        };
+        self.rx_storage = RxStorage::Copy;
+        self.rx_len = 0;
+        previous
+    }
+
+    // frames stay in the driver's buffer and are only lent through received(),
+    // saving RAM and a copy; get_rx stays empty, so LorawanRadio cannot use it
+    pub fn lend_rx(&mut self) -> Option<&'static mut [u8]> {
+        let previous = self.take_rx_buffer();
+        self.rx_storage = RxStorage::Lend;
+        previous
+    }
+
     pub fn send(&mut self, buffer: &mut [u8]) {
+        self.select();
         unsafe {
             if let Some(send) = self.radio.c_handle.Send {
                 send(buffer.as_mut_ptr(), buffer.len() as u8);
@@ -213,28 +716,38 @@ impl Sx12xx {
         };
     }
 
-    pub fn configure_fsk_tx(&mut self, power: i8, fdev: u32, datarate: u32) {
+    fn apply_fsk_settings(&mut self, config: &FskConfig) {
+        let mut sync_word = config.sync_word.clone();
+        self.select();
         unsafe {
-            if let Some(set_tx_config) = self.radio.c_handle.SetTxConfig {
-                set_tx_config(
-                    RadioModems_t_MODEM_FSK,
-                    power,
-                    fdev,
-                    0,
-                    datarate,
-                    0,
-                    self.settings.preamble_len,
-                    self.settings.fix_len,
-                    self.settings.crc_on,
-                    false,
-                    0,
-                    false,
-                    0,
-                )
+            if let Some(set_sync_word) = self.radio.c_handle.SetSyncWord {
+                let len = sync_word.len() as u8;
+                set_sync_word(sync_word.as_mut_ptr(), len);
+            }
+            if let Some(set_whitening) = self.radio.c_handle.SetWhitening {
+                set_whitening(config.whitening);
             }
         };
     }
 
+    pub fn configure_fsk_tx(&mut self, power: i8, fdev: u32, datarate: u32) {
+        let config = FskConfig {
+            power,
+            tx_timeout_ms: 0,
+            ..self.fsk_config(datarate, fdev, 0)
+        };
+        self.write_tx_config(RadioConfig::Fsk(config));
+    }
+
+    // bandwidth and bandwidth_afc in Hz
+    pub fn configure_fsk_rx(&mut self, bandwidth: u32, bandwidth_afc: u32, datarate: u32) {
+        let config = FskConfig {
+            bandwidth_afc,
+            ..self.fsk_config(datarate, 0, bandwidth)
+        };
+        self.write_rx_config(RadioConfig::Fsk(config));
+    }
+
     pub fn configure_lora_tx(
         &mut self,
         power: i8,
@@ -242,32 +755,12 @@ impl Sx12xx {
         datarate: LoRaSpreadingFactor,
         coderate: LoRaCodingRate,
     ) {
-        let (freq_hop_on, hop_period) = if let HopPeriod::Enabled(period) = self.settings.hop_period
-        {
-            (true, period)
-        } else {
-            (false, 0)
-        };
-
-        unsafe {
-            if let Some(set_tx_config) = self.radio.c_handle.SetTxConfig {
-                set_tx_config(
-                    RadioModems_t_MODEM_LORA,     // modem
-                    power,                        // power
-                    0,                            // fdev (is always 0 for LoRa)
-                    bandwidth as u32,             // bandwidth
-                    datarate as u32,              // datarate
-                    coderate as u8,               // coding rate
-                    self.settings.preamble_len,   // preamble len
-                    self.settings.fix_len,        // fix length packet
-                    self.settings.crc_on,         // crc setting
-                    freq_hop_on,                  // frequency hop setting
-                    hop_period,                   // number of symbols before hop
-                    self.settings.iq_inverted.tx, // inverted iq
-                    3000,                         // transmission timeout
-                );
-            }
+        let config = LoRaConfig {
+            power,
+            iq_inverted: self.settings.iq_inverted.tx,
+            ..self.lora_config(bandwidth, datarate, coderate)
         };
+        self.write_tx_config(RadioConfig::LoRa(config));
     }
 
     pub fn configure_lora_rx(
@@ -276,36 +769,264 @@ impl Sx12xx {
         datarate: LoRaSpreadingFactor,
         coderate: LoRaCodingRate,
     ) {
-        let (freq_hop_on, hop_period) = if let HopPeriod::Enabled(period) = self.settings.hop_period
-        {
-            (true, period)
-        } else {
-            (false, 0)
+        let config = LoRaConfig {
+            iq_inverted: self.settings.iq_inverted.rx,
+            ..self.lora_config(bandwidth, datarate, coderate)
+        };
+        self.write_rx_config(RadioConfig::LoRa(config));
+    }
+
+    // the configure_* methods take the rest from the individual setters and,
+    // as always, hand it to the driver unchecked
+    fn lora_config(
+        &self,
+        bandwidth: LoRaBandwidth,
+        datarate: LoRaSpreadingFactor,
+        coderate: LoRaCodingRate,
+    ) -> LoRaConfig {
+        LoRaConfig {
+            preamble_len: self.settings.preamble_len,
+            implicit_header: if self.settings.fix_len {
+                Some(self.settings.payload_len)
+            } else {
+                None
+            },
+            crc_on: self.settings.crc_on,
+            hop_period: match self.settings.hop_period {
+                HopPeriod::Enabled(period) => Some(period),
+                HopPeriod::Disabled => None,
+            },
+            symbol_timeout: self.settings.timeout,
+            continuous_rx: self.settings.continuous_rx,
+            ..LoRaConfig::new(datarate, bandwidth, coderate)
+        }
+    }
+
+    fn fsk_config(&self, datarate: u32, fdev: u32, bandwidth: u32) -> FskConfig {
+        FskConfig {
+            preamble_len: self.settings.preamble_len,
+            fix_len: if self.settings.fix_len {
+                Some(self.settings.payload_len)
+            } else {
+                None
+            },
+            crc_on: self.settings.crc_on,
+            sync_word: self.settings.fsk.sync_word.clone(),
+            whitening: self.settings.fsk.whitening,
+            symbol_timeout: self.settings.timeout,
+            continuous_rx: self.settings.continuous_rx,
+            ..FskConfig::new(datarate, fdev, bandwidth)
+        }
+    }
+
+    // checked against the chip before anything is written, so a rejected
+    // configuration leaves the one in use untouched
+    pub fn apply_tx<C: Into<RadioConfig>>(&mut self, config: C) -> Result<(), Error> {
+        let config = config.into();
+        self.check_config(&config, true)?;
+        if self.radio.c_handle.SetTxConfig.is_none() {
+            return Err(Error::MissingDriverFunction);
+        }
+        self.write_tx_config(config);
+        self.check_busy()
+    }
+
+    pub fn apply_rx<C: Into<RadioConfig>>(&mut self, config: C) -> Result<(), Error> {
+        let config = config.into();
+        self.check_config(&config, false)?;
+        if self.radio.c_handle.SetRxConfig.is_none() {
+            return Err(Error::MissingDriverFunction);
+        }
+        self.write_rx_config(config);
+        self.check_busy()
+    }
+
+    // the configuration in use for TX, however it was applied
+    pub fn get_tx_config(&self) -> Option<&RadioConfig> {
+        self.settings.tx_config.as_ref()
+    }
+
+    pub fn get_rx_config(&self) -> Option<&RadioConfig> {
+        self.settings.rx_config.as_ref()
+    }
+
+    fn check_config(&self, config: &RadioConfig, tx: bool) -> Result<(), Error> {
+        config.validate(self.radio.family, tx)?;
+        if let RadioConfig::LoRa(config) = config {
+            let c_handle = &self.radio.c_handle;
+            if config.needs_registers() && (c_handle.Read.is_none() || c_handle.Write.is_none()) {
+                return Err(Error::MissingDriverFunction);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_tx_config(&mut self, config: RadioConfig) {
+        let tx = match &config {
+            RadioConfig::LoRa(lora) => lora.tx_config(),
+            RadioConfig::Fsk(fsk) => {
+                self.apply_fsk_settings(fsk);
+                fsk.tx_config()
+            }
+        };
+        self.select();
+        unsafe {
+            if let Some(set_tx_config) = self.radio.c_handle.SetTxConfig {
+                set_tx_config(
+                    tx.modem,
+                    tx.power,
+                    tx.fdev,
+                    tx.bandwidth,
+                    tx.datarate,
+                    tx.coderate,
+                    tx.preamble_len,
+                    tx.fix_len,
+                    tx.crc_on,
+                    tx.freq_hop_on,
+                    tx.hop_period,
+                    tx.iq_inverted,
+                    tx.timeout,
+                );
+            }
         };
+        if let RadioConfig::LoRa(lora) = &config {
+            self.write_lora_registers(lora);
+        }
+        self.settings.tx_config = Some(config);
+        self.settings.rx_configured_last = false;
+    }
 
+    fn write_rx_config(&mut self, config: RadioConfig) {
+        self.program_rx_config(&config);
+        self.settings.rx_config = Some(config);
+        self.settings.rx_configured_last = true;
+        self.settings.rx_single_written = false;
+    }
+
+    // hands config to the drivers without making it the configuration in use
+    fn program_rx_config(&mut self, config: &RadioConfig) {
+        let rx = match config {
+            RadioConfig::LoRa(lora) => lora.rx_config(),
+            RadioConfig::Fsk(fsk) => {
+                self.apply_fsk_settings(fsk);
+                fsk.rx_config()
+            }
+        };
+        self.select();
         unsafe {
             if let Some(set_rx_config) = self.radio.c_handle.SetRxConfig {
                 set_rx_config(
-                    RadioModems_t_MODEM_LORA,   // modem
-                    bandwidth as u32,           // bandwidth
-                    datarate as u32,            // datarate
-                    coderate as u8,             // coding rate
-                    0,                          // bandwidth afc (FSK only)
-                    self.settings.preamble_len, // preamble len
-                    self.settings.timeout,
-                    self.settings.fix_len,        // fix length packet,
-                    0,                            // packet length if fixed
-                    self.settings.crc_on,         // crc setting
-                    freq_hop_on,                  // frequency hop setting
-                    hop_period,                   // number of symbols before hop
-                    self.settings.iq_inverted.rx, // inverted iq
-                    self.settings.continuous_rx,  // transmission timeout
+                    rx.modem,
+                    rx.bandwidth,
+                    rx.datarate,
+                    rx.coderate,
+                    rx.bandwidth_afc,
+                    rx.preamble_len,
+                    rx.symb_timeout,
+                    rx.fix_len,
+                    rx.payload_len,
+                    rx.crc_on,
+                    rx.freq_hop_on,
+                    rx.hop_period,
+                    rx.iq_inverted,
+                    rx.rx_continuous,
                 );
             }
         };
+        self.settings.rx_modem = match config {
+            RadioConfig::LoRa(lora) => {
+                self.write_lora_registers(lora);
+                Modem::LoRa
+            }
+            RadioConfig::Fsk(_) => Modem::Fsk,
+        };
+    }
+
+    // receptions other than set_rx_single run on the configuration in use
+    fn undo_rx_single(&mut self) {
+        if self.settings.rx_single_written {
+            if let Some(rx) = self.settings.rx_config.clone() {
+                self.write_rx_config(rx);
+            }
+        }
+    }
+
+    // SetTxConfig/SetRxConfig leave the sync word to set_public_network and
+    // rewrite low data rate optimisation every time, so both overrides follow
+    // them. The SX127x drivers compute time on air with their own LDRO choice
+    fn write_lora_registers(&mut self, config: &LoRaConfig) {
+        let (read, write) = match (self.radio.c_handle.Read, self.radio.c_handle.Write) {
+            (Some(read), Some(write)) => (read, write),
+            _ => return,
+        };
+        unsafe {
+            match (self.radio.family, config.low_data_rate_optimize) {
+                // RegModemConfig1
+                (Family::Sx1272, Some(on)) => {
+                    let value = read(0x1D) & 0xFE;
+                    write(0x1D, if on { value | 0x01 } else { value });
+                }
+                // RegModemConfig3
+                (Family::Sx1276, Some(on)) => {
+                    let value = read(0x26) & 0xF7;
+                    write(0x26, if on { value | 0x08 } else { value });
+                }
+                // only ever the drivers' own choice, see LoRaConfig::validate
+                _ => (),
+            }
+            if let Some(sync_word) = config.sync_word {
+                match self.radio.family {
+                    Family::Sx1272 | Family::Sx1276 => write(0x39, sync_word),
+                    // each nibble is followed by 0x4, e.g. 0x34 becomes 0x3444
+                    Family::Sx126x => {
+                        write(0x0740, (sync_word & 0xF0) | 0x04);
+                        write(0x0741, (sync_word << 4) | 0x04);
+                    }
+                }
+            }
+        }
+    }
+
+    // the drivers configure a single modem at a time, so the configuration
+    // applied last is applied last again
+    fn restore_config(&mut self) {
+        let (tx, rx) = (
+            self.settings.tx_config.clone(),
+            self.settings.rx_config.clone(),
+        );
+        if self.settings.rx_configured_last {
+            if let Some(tx) = tx {
+                self.write_tx_config(tx);
+            }
+            if let Some(rx) = rx {
+                self.write_rx_config(rx);
+            }
+        } else {
+            if let Some(rx) = rx {
+                self.write_rx_config(rx);
+            }
+            if let Some(tx) = tx {
+                self.write_tx_config(tx);
+            }
+        }
+    }
+
+    // 32 bits of wideband RSSI noise; the drivers switch to LoRa and leave
+    // the radio asleep, so the previous configuration is applied again and
+    // any reception has to be restarted
+    pub fn random_u32(&mut self) -> Result<u32, Error> {
+        self.select();
+        let rnd = match self.radio.c_handle.Random {
+            Some(random) => unsafe { random() },
+            None => return Err(Error::MissingDriverFunction),
+        };
+        self.restore_config();
+        self.check_busy()?;
+        Ok(rnd)
     }
 
     pub fn sleep(&mut self) {
+        self.select();
         unsafe {
             if let Some(sleep) = self.radio.c_handle.Sleep {
                 sleep()
@@ -314,6 +1035,7 @@ impl Sx12xx {
     }
 
     pub fn standby(&mut self) {
+        self.select();
         unsafe {
             if let Some(standby) = self.radio.c_handle.Standby {
                 standby()
@@ -322,6 +1044,9 @@ impl Sx12xx {
     }
 
     pub fn set_rx(&mut self) {
+        self.rx_single = false;
+        self.undo_rx_single();
+        self.select();
         unsafe {
             if let Some(rx) = self.radio.c_handle.Rx {
                 rx(0)
@@ -329,15 +1054,246 @@ impl Sx12xx {
         };
     }
 
+    // a single reception that gives up after symbol_timeout symbols (LoRa) or
+    // bytes (FSK) without a preamble; the drivers get the RX configuration in
+    // single mode for this reception only, get_rx_config and later receptions
+    // are unaffected. The outcome is reported as Response::RxDone, RxError or
+    // RxTimeout
+    pub fn set_rx_single(&mut self, symbol_timeout: u16) -> Result<(), Error> {
+        let mut config = self.settings.rx_config.clone().ok_or(Error::NoRxConfig)?;
+        match config {
+            RadioConfig::LoRa(ref mut config) => {
+                config.symbol_timeout = symbol_timeout;
+                config.continuous_rx = false;
+            }
+            RadioConfig::Fsk(ref mut config) => {
+                config.symbol_timeout = symbol_timeout;
+                config.continuous_rx = false;
+            }
+        }
+        self.check_config(&config, false)?;
+        if self.radio.c_handle.SetRxConfig.is_none() {
+            return Err(Error::MissingDriverFunction);
+        }
+        self.program_rx_config(&config);
+        self.settings.rx_single_written = true;
+        self.check_busy()?;
+        self.start_rx_single(0)
+    }
+
+    // a reception that ends with the first frame or, failing that, after
+    // timeout_ms with Response::RxTimeout; the radio is left in standby
+    pub fn set_rx_timeout(&mut self, timeout_ms: u32) -> Result<(), Error> {
+        if timeout_ms == 0 {
+            return Err(Error::InvalidConfig);
+        }
+        self.undo_rx_single();
+        self.start_rx_single(timeout_ms)
+    }
+
+    // RX with the LNA at maximum gain for about 3 dB of sensitivity and 2 mA
+    // more (SX126x only); timeout_ms as for set_rx_timeout, 0 to receive
+    // until told otherwise
+    pub fn set_rx_boosted(&mut self, timeout_ms: u32) -> Result<(), Error> {
+        self.undo_rx_single();
+        self.select();
+        match self.radio.c_handle.RxBoosted {
+            Some(rx_boosted) => unsafe { rx_boosted(timeout_ms) },
+            None => return Err(Error::Unsupported),
+        }
+        self.check_busy()?;
+        self.rx_single = timeout_ms != 0;
+        Ok(())
+    }
+
+    // sniff mode (SX126x only): the chip alternates between listening for
+    // rx_time_us and sleeping for sleep_time_us until a preamble is found,
+    // then receives the frame as configured. Both times are rounded down to
+    // 15.625 us steps and are limited to 2^24 steps (262 s)
+    pub fn set_rx_duty_cycle(&mut self, rx_time_us: u32, sleep_time_us: u32) -> Result<(), Error> {
+        let steps = |us: u32| (us as u64 * 64 / 1000) as u32;
+        let (rx_time, sleep_time) = (steps(rx_time_us), steps(sleep_time_us));
+        if rx_time == 0 || rx_time > 0xFF_FFFF || sleep_time > 0xFF_FFFF {
+            return Err(Error::InvalidConfig);
+        }
+        self.undo_rx_single();
+        self.select();
+        match self.radio.c_handle.SetRxDutyCycle {
+            Some(set_rx_duty_cycle) => unsafe { set_rx_duty_cycle(rx_time, sleep_time) },
+            None => return Err(Error::Unsupported),
+        }
+        self.check_busy()?;
+        self.rx_single = false;
+        Ok(())
+    }
+
+    fn start_rx_single(&mut self, timeout_ms: u32) -> Result<(), Error> {
+        self.select();
+        match self.radio.c_handle.Rx {
+            Some(rx) => unsafe { rx(timeout_ms) },
+            None => return Err(Error::MissingDriverFunction),
+        }
+        self.check_busy()?;
+        self.rx_single = true;
+        Ok(())
+    }
+
+    // unmodulated carrier for duration_s seconds, for RF certification;
+    // completion is reported as Response::TestModeDone
+    pub fn start_cw(&mut self, frequency: u32, power: i8, duration_s: u16) -> Result<(), Error> {
+        self.select();
+        match self.radio.c_handle.SetTxContinuousWave {
+            Some(set_tx_continuous_wave) => unsafe {
+                set_tx_continuous_wave(frequency, power, duration_s)
+            },
+            None => return Err(Error::MissingDriverFunction),
+        }
+        self.check_busy()?;
+        self.test_mode = true;
+        Ok(())
+    }
+
+    // endless preamble with the modulation of the last configuration
+    // (SX126x only); completion is reported as Response::TestModeDone
+    pub fn start_infinite_preamble(
+        &mut self,
+        frequency: u32,
+        power: i8,
+        duration_s: u16,
+    ) -> Result<(), Error> {
+        self.select();
+        match self.radio.c_handle.SetTxInfinitePreamble {
+            Some(set_tx_infinite_preamble) => unsafe {
+                set_tx_infinite_preamble(frequency, power, duration_s)
+            },
+            None => return Err(Error::MissingDriverFunction),
+        }
+        self.check_busy()?;
+        self.test_mode = true;
+        Ok(())
+    }
+
+    // ends a test early; None if none was running
+    pub fn stop_test_mode(&mut self) -> Option<Response> {
+        if self.test_mode {
+            self.end_test_mode();
+            Some(Response::TestModeDone)
+        } else {
+            None
+        }
+    }
+
+    // the SX127x drivers reset the chip when the test times out and the test
+    // modes overwrite the TX configuration, so it is applied again
+    fn end_test_mode(&mut self) {
+        self.test_mode = false;
+        self.standby();
+        self.restore_config();
+    }
+
+    // uses the spreading factor and bandwidth of the last LoRa configuration;
+    // completion is reported as Response::CadDone
+    pub fn start_cad(&mut self) {
+        self.select();
+        unsafe {
+            if let Some(start_cad) = self.radio.c_handle.StartCad {
+                start_cad()
+            }
+        };
+    }
+
     pub fn set_frequency(&mut self, frequency_mhz: u32) {
+        self.select();
         unsafe {
             if let Some(set_channel) = self.radio.c_handle.SetChannel {
                 set_channel(frequency_mhz);
             }
         };
+        self.settings.frequency = frequency_mhz;
+    }
+
+    // last frequency set, in Hz
+    pub fn get_frequency(&self) -> u32 {
+        self.settings.frequency
+    }
+
+    // whether the board runs the drivers' timeout timers
+    pub(crate) fn has_timers(&self) -> bool {
+        let bindings = &self.handle.0.bindings;
+        bindings.timer_start.is_some() && bindings.timer_stop.is_some()
+    }
+
+    // the board's millisecond clock, if it has one
+    pub(crate) fn now_ms(&mut self) -> Option<u32> {
+        self.select();
+        let bindings = &self.handle.0.bindings;
+        bindings
+            .get_time_ms
+            .map(|get_time_ms| unsafe { get_time_ms(bindings.context) })
+    }
+
+    // in ms, from the driver's own calculation for the configuration applied
+    // last (TX or RX)
+    pub fn time_on_air(&mut self, len: u8) -> Result<u32, Error> {
+        let modem = if self.settings.rx_configured_last {
+            self.settings.rx_config.as_ref().map(RadioConfig::modem)
+        } else {
+            self.settings.tx_config.as_ref().map(RadioConfig::modem)
+        };
+        let modem = modem.unwrap_or(RadioModems_t_MODEM_LORA);
+        self.select();
+        match self.radio.c_handle.TimeOnAir {
+            Some(time_on_air) => Ok(unsafe { time_on_air(modem, len) }),
+            None => Err(Error::MissingDriverFunction),
+        }
+    }
+
+    // senses with the modem of the last TX configuration and leaves the radio
+    // asleep on the given frequency; without BoardBindings::get_time_ms the
+    // sense time is measured in DelayMs calls
+    pub fn is_channel_free(
+        &mut self,
+        frequency: u32,
+        threshold_dbm: i16,
+        sense_time_ms: u32,
+    ) -> Result<bool, Error> {
+        let modem = self
+            .settings
+            .tx_config
+            .as_ref()
+            .map_or(RadioModems_t_MODEM_LORA, RadioConfig::modem);
+        self.select();
+        let status = match self.radio.c_handle.GetStatus {
+            Some(get_status) => unsafe { get_status() },
+            None => return Err(Error::MissingDriverFunction),
+        };
+        if status != RadioState_t_RF_IDLE {
+            return Err(Error::RadioNotIdle);
+        }
+        let free = match self.radio.c_handle.IsChannelFree {
+            Some(is_channel_free) => unsafe {
+                is_channel_free(modem, frequency, threshold_dbm, sense_time_ms)
+            },
+            None => return Err(Error::MissingDriverFunction),
+        };
+        self.settings.frequency = frequency;
+        self.check_busy()?;
+        Ok(free)
+    }
+
+    // sends on the current frequency only if carrier sense finds it free
+    pub fn send_lbt(&mut self, buffer: &mut [u8], lbt: Lbt) -> Result<(), Error> {
+        let frequency = self.settings.frequency;
+        if self.is_channel_free(frequency, lbt.threshold_dbm, lbt.sense_time_ms)? {
+            self.send(buffer);
+            self.check_busy()
+        } else {
+            Err(Error::ChannelBusy)
+        }
     }
 
     pub fn set_public_network(&mut self, enable: bool) {
+        self.select();
         unsafe {
             if let Some(set_public_network) = self.radio.c_handle.SetPublicNetwork {
                 set_public_network(enable);
@@ -349,6 +1305,14 @@ impl Sx12xx {
         self.settings.hop_period = HopPeriod::Enabled(period);
     }
 
+    // frequencies in Hz, used in turn on every FhssChangeChannel interrupt;
+    // set_frequency should still be called with the first entry before TX/RX
+    pub fn set_hop_table(&mut self, frequencies: &[u32]) -> Result<(), Error> {
+        self.settings.hop_table =
+            Vec::from_slice(frequencies).map_err(|_| Error::InvalidHopTable)?;
+        Ok(())
+    }
+
     pub fn disable_hop_period(&mut self) {
         self.settings.hop_period = HopPeriod::Disabled;
     }
@@ -361,10 +1325,164 @@ impl Sx12xx {
         self.settings.iq_inverted.rx = set;
     }
 
+    // None for variable length packets (explicit header in LoRa)
+    pub fn set_fixed_payload_len(&mut self, len: Option<u8>) {
+        if let Some(len) = len {
+            self.settings.fix_len = true;
+            self.settings.payload_len = len;
+        } else {
+            self.settings.fix_len = false;
+            self.settings.payload_len = 0;
+        }
+    }
+
+    // up to 8 bytes; an empty sync word disables sync word detection
+    pub fn set_fsk_sync_word(&mut self, sync_word: &[u8]) -> Result<(), Error> {
+        self.settings.fsk.sync_word =
+            Vec::from_slice(sync_word).map_err(|_| Error::InvalidSyncWord)?;
+        Ok(())
+    }
+
+    pub fn set_fsk_whitening(&mut self, enable: bool) {
+        self.settings.fsk.whitening = enable;
+    }
+
     // timeout in FSK bytes or LoRa symboles
     pub fn set_timeout(&mut self, timeout: u16) {
         self.settings.timeout = timeout;
     }
+
+    // Raw register access for tweaks the API does not cover. The drivers are
+    // not told, so later configuration calls may undo them. Addresses are 7
+    // bits on SX127x and 16 bits on SX126x
+    pub fn read_register(&mut self, address: u16) -> Result<u8, Error> {
+        self.select();
+        let value = match self.radio.c_handle.Read {
+            Some(read) => unsafe { read(address) },
+            None => return Err(Error::MissingDriverFunction),
+        };
+        self.check_busy()?;
+        Ok(value)
+    }
+
+    pub fn write_register(&mut self, address: u16, value: u8) -> Result<(), Error> {
+        self.select();
+        match self.radio.c_handle.Write {
+            Some(write) => unsafe { write(address, value) },
+            None => return Err(Error::MissingDriverFunction),
+        }
+        self.check_busy()
+    }
+
+    // consecutive registers from address; on SX127x bursts at the FIFO
+    // (0x00) stay on it
+    pub fn read_registers(&mut self, address: u16, buffer: &mut [u8]) -> Result<(), Error> {
+        let read_buffer = self
+            .radio
+            .c_handle
+            .ReadBuffer
+            .ok_or(Error::MissingDriverFunction)?;
+        self.select();
+        for (i, chunk) in buffer.chunks_mut(255).enumerate() {
+            let address = self.burst_address(address, i * 255);
+            unsafe { read_buffer(address, chunk.as_mut_ptr(), chunk.len() as u8) };
+        }
+        self.check_busy()
+    }
+
+    pub fn write_registers(&mut self, address: u16, buffer: &[u8]) -> Result<(), Error> {
+        let write_buffer = self
+            .radio
+            .c_handle
+            .WriteBuffer
+            .ok_or(Error::MissingDriverFunction)?;
+        self.select();
+        for (i, chunk) in buffer.chunks(255).enumerate() {
+            let address = self.burst_address(address, i * 255);
+            // the drivers only read from the buffer
+            unsafe { write_buffer(address, chunk.as_ptr() as *mut u8, chunk.len() as u8) };
+        }
+        self.check_busy()
+    }
+
+    fn burst_address(&self, address: u16, offset: usize) -> u16 {
+        match self.radio.family {
+            Family::Sx1272 | Family::Sx1276 if address == 0 => 0,
+            _ => address.wrapping_add(offset as u16),
+        }
+    }
+
+    // typed access through the maps in regs, which are SX127x only; take the
+    // map of the chip in use, as the SX1272 and SX1276 differ
+    pub fn read(&mut self, register: Register) -> Result<u8, Error> {
+        self.check_sx127x()?;
+        self.read_register(register.into())
+    }
+
+    pub fn write(&mut self, register: Register, value: u8) -> Result<(), Error> {
+        self.check_sx127x()?;
+        self.write_register(register.into(), value)
+    }
+
+    // the bits of the field, left in place
+    pub fn read_field(&mut self, field: Field) -> Result<u8, Error> {
+        Ok(field.get(self.read(field.register)?))
+    }
+
+    // read-modify-write of the register holding the field
+    pub fn write_field(&mut self, value: FieldValue) -> Result<(), Error> {
+        let register = value.field.register;
+        let current = self.read(register)?;
+        self.write(register, value.field.set(current, value.bits))
+    }
+
+    // registers 0x01 to 0x7F of an SX127x, for field debugging; the FIFO at
+    // 0x00 is left out as reading it moves the FIFO pointer
+    pub fn dump_registers(&mut self) -> Result<[u8; 0x80], Error> {
+        self.check_sx127x()?;
+        let mut registers = [0; 0x80];
+        self.read_registers(0x01, &mut registers[1..])?;
+        Ok(registers)
+    }
+
+    fn check_sx127x(&self) -> Result<(), Error> {
+        match self.radio.family {
+            Family::Sx1272 | Family::Sx1276 => Ok(()),
+            Family::Sx126x => Err(Error::Unsupported),
+        }
+    }
+}
+
+impl<B> Drop for Sx12xx<B> {
+    fn drop(&mut self) {
+        self.radio.family.in_use().store(false, Ordering::Release);
+    }
+}
+
+// not a CryptoRng: the noise is sampled with no conditioning
+impl<B: Board> RngCore for Sx12xx<B> {
+    // panics if the radio gives no noise; see Sx12xx
+    fn next_u32(&mut self) -> u32 {
+        self.random_u32().expect("radio driver provides no Random")
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        for chunk in dest.chunks_mut(4) {
+            let rnd = self.random_u32().map_err(|_| {
+                rand_core::Error::from(NonZeroU32::new(rand_core::Error::CUSTOM_START).unwrap())
+            })?;
+            chunk.copy_from_slice(&rnd.to_le_bytes()[..chunk.len()]);
+        }
+        Ok(())
+    }
 }
 
 #[no_mangle]
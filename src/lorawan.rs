@@ -1,4 +1,4 @@
-use super::{Error, Response, Sx12xx};
+use super::{Board, DutyCycle, Error, Lbt, PacketQuality, Response, Sx12xx};
 use lorawan_device::radio::{Error as LoraError, Event as LoraEvent, Response as LoraResponse};
 
 #[derive(Debug, Copy, Clone)]
@@ -10,23 +10,32 @@ pub enum State {
 
 use core::convert::From;
 
+// RX windows are closed by the drivers' timeout timers (BoardBindings
+// timer_start and timer_stop, or Hal::timers). Without them the radio
+// listens until a frame arrives or lorawan-device sends CancelRx, which it
+// only does if the application feeds it TimeoutFired for every
+// TimeoutRequest
 #[derive(Debug)]
-pub struct LorawanRadio {
-    sx12xx: Sx12xx,
+pub struct LorawanRadio<B = ()> {
+    sx12xx: Sx12xx<B>,
     radio_state: State,
     rx_window_offset_ms: i32,
     rx_window_duration_ms: u32,
+    lbt: Option<Lbt>,
+    duty_cycle: Option<DutyCycle>,
 }
 
 use core::default::Default;
 
-impl LorawanRadio {
-    pub fn new(sx12xx: Sx12xx) -> Self {
+impl<B: Board> LorawanRadio<B> {
+    pub fn new(sx12xx: Sx12xx<B>) -> Self {
         Self {
             sx12xx,
             radio_state: State::Idle(Idle::default()),
             rx_window_offset_ms: -500,
             rx_window_duration_ms: 800,
+            lbt: None,
+            duty_cycle: None,
         }
     }
 
@@ -38,7 +47,20 @@ impl LorawanRadio {
         self.rx_window_duration_ms = rx_window_duration_ms;
     }
 
-    pub fn get_sx12xx(&mut self) -> &mut Sx12xx {
+    // carrier sense before every uplink, as required in e.g. AS923 Japan and
+    // KR920; a busy channel fails the TxRequest with Error::ChannelBusy
+    pub fn set_lbt(&mut self, lbt: Option<Lbt>) {
+        self.lbt = lbt;
+    }
+
+    // refuse uplinks that would break regional duty-cycle or dwell-time
+    // limits; a refused TxRequest fails with Error::DutyCycleExceeded or
+    // Error::DwellTimeExceeded
+    pub fn set_duty_cycle(&mut self, duty_cycle: Option<DutyCycle>) {
+        self.duty_cycle = duty_cycle;
+    }
+
+    pub fn get_sx12xx(&mut self) -> &mut Sx12xx<B> {
         &mut self.sx12xx
     }
 }
@@ -83,13 +105,16 @@ impl Default for Idle {
 }
 
 impl Idle {
-    fn handle_event(
+    fn handle_event<B: Board>(
         self,
-        sx12xx: &mut Sx12xx,
-        event: LoraEvent<LorawanRadio>,
+        sx12xx: &mut Sx12xx<B>,
+        lbt: Option<Lbt>,
+        duty_cycle: Option<&mut DutyCycle>,
+        rx_window_duration_ms: u32,
+        event: LoraEvent<LorawanRadio<B>>,
     ) -> (
         State,
-        Result<LoraResponse<LorawanRadio>, LoraError<LorawanRadio>>,
+        Result<LoraResponse<LorawanRadio<B>>, LoraError<LorawanRadio<B>>>,
     ) {
         match event {
             LoraEvent::TxRequest(tx_config, buf) => {
@@ -101,7 +126,17 @@ impl Idle {
                     tx_config.rf.coding_rate.into(),
                 );
                 let len = buf.len();
-                sx12xx.send(buf[..len].as_mut());
+                let sent = match (duty_cycle, lbt) {
+                    (Some(duty_cycle), lbt) => duty_cycle.send(sx12xx, buf[..len].as_mut(), lbt),
+                    (None, Some(lbt)) => sx12xx.send_lbt(buf[..len].as_mut(), lbt),
+                    (None, None) => {
+                        sx12xx.send(buf[..len].as_mut());
+                        Ok(())
+                    }
+                };
+                if let Err(e) = sent {
+                    return (State::Idle(self), Err(LoraError::PhyError(e)));
+                }
                 (State::Txing(self.into()), Ok(LoraResponse::Txing))
             }
             LoraEvent::RxRequest(config) => {
@@ -111,8 +146,16 @@ impl Idle {
                     config.spreading_factor.into(),
                     config.coding_rate.into(),
                 );
-                sx12xx.set_rx();
-
+                // a window takes a single frame and closes by itself, given
+                // timers to close it with
+                if sx12xx.has_timers() {
+                    if let Err(e) = sx12xx.set_rx_timeout(rx_window_duration_ms) {
+                        sx12xx.sleep();
+                        return (State::Idle(self), Err(LoraError::PhyError(e)));
+                    }
+                } else {
+                    sx12xx.set_rx();
+                }
                 (State::Rxing(self.into()), Ok(LoraResponse::Rxing))
             }
             // deny any events while idle; they are unexpected
@@ -127,26 +170,37 @@ impl Idle {
 
 state![Txing; [Idle]];
 impl Txing {
-    fn handle_event(
+    fn handle_event<B: Board>(
         self,
-        sx12xx: &mut Sx12xx,
-        event: LoraEvent<LorawanRadio>,
+        sx12xx: &mut Sx12xx<B>,
+        event: LoraEvent<LorawanRadio<B>>,
     ) -> (
         State,
-        Result<LoraResponse<LorawanRadio>, LoraError<LorawanRadio>>,
+        Result<LoraResponse<LorawanRadio<B>>, LoraError<LorawanRadio<B>>>,
     ) {
         match event {
-            LoraEvent::PhyEvent(phyevent) => {
-                if let Response::TxDone(timestamp_ms) = sx12xx.handle_event(phyevent) {
+            LoraEvent::PhyEvent(phyevent) => match sx12xx.handle_event(phyevent) {
+                Ok(Response::TxDone(timestamp_ms)) => {
                     sx12xx.standby();
                     (
                         State::Idle(self.into()),
                         Ok(LoraResponse::TxDone(timestamp_ms)),
                     )
-                } else {
-                    (State::Txing(self), Ok(LoraResponse::Txing))
                 }
-            }
+                Ok(Response::TxTimeout) => {
+                    sx12xx.sleep();
+                    (
+                        State::Idle(self.into()),
+                        Err(LoraError::PhyError(Error::TxTimeout)),
+                    )
+                }
+                Ok(_) => (State::Txing(self), Ok(LoraResponse::Txing)),
+                // whatever went wrong, the transmission is over
+                Err(e) => {
+                    sx12xx.sleep();
+                    (State::Idle(self.into()), Err(LoraError::PhyError(e)))
+                }
+            },
             LoraEvent::TxRequest(_, _) => (State::Txing(self), Err(LoraError::TxRequestDuringTx)),
             LoraEvent::RxRequest(_) => (State::Txing(self), Err(LoraError::RxRequestDuringTx)),
             LoraEvent::CancelRx => (State::Txing(self), Err(LoraError::CancelRxDuringTx)),
@@ -156,23 +210,46 @@ impl Txing {
 
 state![Rxing; [Idle]];
 impl Rxing {
-    fn handle_event(
+    fn handle_event<B: Board>(
         self,
-        sx12xx: &mut Sx12xx,
-        event: LoraEvent<LorawanRadio>,
+        sx12xx: &mut Sx12xx<B>,
+        event: LoraEvent<LorawanRadio<B>>,
     ) -> (
         State,
-        Result<LoraResponse<LorawanRadio>, LoraError<LorawanRadio>>,
+        Result<LoraResponse<LorawanRadio<B>>, LoraError<LorawanRadio<B>>>,
     ) {
         match event {
-            LoraEvent::PhyEvent(phyevent) => {
-                if let Response::RxDone(_, quality) = sx12xx.handle_event(phyevent) {
+            LoraEvent::PhyEvent(phyevent) => match sx12xx.handle_event(phyevent) {
+                Ok(Response::RxDone(_, PacketQuality::LoRa(quality))) => {
                     sx12xx.sleep();
                     (State::Idle(self.into()), Ok(LoraResponse::RxDone(quality)))
-                } else {
-                    (State::Rxing(self), Ok(LoraResponse::Rxing))
                 }
-            }
+                // nothing arrived; the window closes as if cancelled
+                Ok(Response::RxTimeout) => {
+                    sx12xx.sleep();
+                    (State::Idle(self.into()), Ok(LoraResponse::Idle))
+                }
+                Ok(Response::RxError) => {
+                    sx12xx.sleep();
+                    (
+                        State::Idle(self.into()),
+                        Err(LoraError::PhyError(Error::RxError)),
+                    )
+                }
+                // someone set up FSK behind LorawanRadio's back
+                Ok(Response::RxDone(_, PacketQuality::Fsk { .. })) => {
+                    sx12xx.sleep();
+                    (
+                        State::Idle(self.into()),
+                        Err(LoraError::PhyError(Error::NotLoRa)),
+                    )
+                }
+                Ok(_) => (State::Rxing(self), Ok(LoraResponse::Rxing)),
+                Err(e) => {
+                    sx12xx.sleep();
+                    (State::Idle(self.into()), Err(LoraError::PhyError(e)))
+                }
+            },
             LoraEvent::CancelRx => {
                 sx12xx.sleep();
                 (State::Idle(self.into()), Ok(LoraResponse::Idle))
@@ -185,7 +262,7 @@ impl Rxing {
 
 use heapless::{consts::*, Vec};
 
-impl lorawan_device::radio::PhyRxTx for LorawanRadio {
+impl<B: Board> lorawan_device::radio::PhyRxTx for LorawanRadio<B> {
     type PhyEvent = super::Event;
     type PhyResponse = super::Response;
     type PhyError = super::Error;
@@ -203,7 +280,13 @@ impl lorawan_device::radio::PhyRxTx for LorawanRadio {
         event: LoraEvent<Self>,
     ) -> Result<LoraResponse<Self>, LoraError<Self>> {
         let (new_state, response) = match &self.radio_state {
-            State::Idle(state) => state.handle_event(&mut self.sx12xx, event),
+            State::Idle(state) => state.handle_event(
+                &mut self.sx12xx,
+                self.lbt,
+                self.duty_cycle.as_mut(),
+                self.rx_window_duration_ms,
+                event,
+            ),
             State::Txing(state) => state.handle_event(&mut self.sx12xx, event),
             State::Rxing(state) => state.handle_event(&mut self.sx12xx, event),
         };
@@ -212,7 +295,7 @@ impl lorawan_device::radio::PhyRxTx for LorawanRadio {
     }
 }
 
-impl lorawan_device::Timings for LorawanRadio {
+impl<B> lorawan_device::Timings for LorawanRadio<B> {
     fn get_rx_window_offset_ms(&self) -> i32 {
         self.rx_window_offset_ms
     }
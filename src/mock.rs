@@ -0,0 +1,469 @@
+// Simulated radio for exercising Sx12xx and LorawanRadio on the host.
+//
+// Radio::mock() stands in for Radio::sx1276(); a Mock, taken for the length
+// of a test, records what the application sends, injects received frames and
+// hands out the Events the application should feed back into handle_event.
+// Like the C drivers it replaces, the simulated radio is a single global, so
+// Mock::take() also keeps tests that use it from running concurrently.
+use super::{
+    BoardBindings, Event, FskAirtime, LoRaAirtime, LoRaBandwidth, LoRaCodingRate,
+    LoRaSpreadingFactor, PaSelect,
+};
+use core::ffi::c_void;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::vec::Vec;
+use sx12xx_sys::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxFrame {
+    pub data: Vec<u8>,
+    pub frequency: u32,
+    pub power: i8,
+    pub bandwidth: u32,
+    pub datarate: u32,
+    pub coderate: u8,
+    pub iq_inverted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Idle,
+    Tx,
+    Rx,
+    Cad,
+}
+
+// what the next DIO interrupt reports
+#[derive(Debug)]
+enum Irq {
+    TxDone,
+    RxDone { rssi: i16, snr: i8 },
+    RxTimeout,
+    CadDone,
+}
+
+struct State {
+    events: *mut RadioEvents_t,
+    status: Status,
+    time_ms: u32,
+    frequency: u32,
+    tx_config: (i8, u32, u32, u8, bool),
+    // modem, preamble length, fixed length, CRC
+    tx_packet: (RadioModems_t, u16, bool, bool),
+    rx_continuous: bool,
+    // end of a reception started with a timeout
+    rx_deadline_ms: Option<u32>,
+    channel_activity: bool,
+    channel_rssi: i16,
+    rng: u32,
+    tx_frames: VecDeque<TxFrame>,
+    // a fixed array, so that frames lent through Sx12xx::received stay put
+    rx_frame: [u8; 256],
+    rx_len: usize,
+    irqs: VecDeque<Irq>,
+    pending: VecDeque<Event>,
+}
+
+// the events pointer is only dereferenced while the state is locked
+unsafe impl Send for State {}
+
+impl State {
+    const fn new() -> State {
+        State {
+            events: core::ptr::null_mut(),
+            status: Status::Idle,
+            time_ms: 0,
+            frequency: 0,
+            tx_config: (0, 0, 0, 0, false),
+            tx_packet: (RadioModems_t_MODEM_LORA, 8, false, true),
+            rx_continuous: false,
+            rx_deadline_ms: None,
+            channel_activity: false,
+            channel_rssi: -120,
+            rng: 0x2545_F491,
+            tx_frames: VecDeque::new(),
+            rx_frame: [0; 256],
+            rx_len: 0,
+            irqs: VecDeque::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn raise(&mut self, irq: Irq, event: Event) {
+        self.irqs.push_back(irq);
+        self.pending.push_back(event);
+    }
+}
+
+static STATE: Mutex<State> = Mutex::new(State::new());
+static TAKEN: Mutex<()> = Mutex::new(());
+
+fn state() -> MutexGuard<'static, State> {
+    // a test that panicked while holding the state leaves nothing worth keeping
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub struct Mock {
+    _taken: MutexGuard<'static, ()>,
+}
+
+impl Mock {
+    // blocks until no other Mock is alive and resets the simulated radio
+    pub fn take() -> Mock {
+        let taken = TAKEN.lock().unwrap_or_else(|e| e.into_inner());
+        *state() = State::new();
+        Mock { _taken: taken }
+    }
+
+    // the simulated radio needs none of the board callbacks but the clock; it
+    // times receptions out by itself, so its timers do nothing
+    pub fn bindings(&self) -> BoardBindings {
+        BoardBindings {
+            context: core::ptr::null_mut(),
+            spi_in_out: None,
+            spi_nss: None,
+            reset: None,
+            delay_ms: None,
+            busy_pin_status: None,
+            reduce_power: None,
+            pa_select: PaSelect::PaSelectAuto,
+            set_board_tcxo: None,
+            set_antenna_pins: None,
+            timer_start: Some(timer_start),
+            timer_stop: Some(timer_stop),
+            get_time_ms: Some(get_time_ms),
+        }
+    }
+
+    // timestamp carried by the Events handed out from now on; a reception
+    // started with a timeout that runs out meanwhile times out on DIO1
+    pub fn advance_time(&mut self, ms: u32) {
+        let mut state = state();
+        state.time_ms = state.time_ms.wrapping_add(ms);
+        if let Some(deadline_ms) = state.rx_deadline_ms {
+            if state.status == Status::Rx && (state.time_ms.wrapping_sub(deadline_ms) as i32) >= 0 {
+                state.rx_deadline_ms = None;
+                state.raise(Irq::RxTimeout, Event::DIO1(deadline_ms));
+            }
+        }
+    }
+
+    pub fn frequency(&self) -> u32 {
+        state().frequency
+    }
+
+    pub fn is_receiving(&self) -> bool {
+        state().status == Status::Rx
+    }
+
+    // oldest frame sent by the application
+    pub fn take_tx(&mut self) -> Option<TxFrame> {
+        state().tx_frames.pop_front()
+    }
+
+    // next interrupt to feed into Sx12xx::handle_event
+    pub fn next_event(&mut self) -> Option<Event> {
+        state().pending.pop_front()
+    }
+
+    // delivers a frame if the radio is listening; returns false otherwise
+    pub fn inject_rx(&mut self, data: &[u8], rssi: i16, snr: i8) -> bool {
+        let mut state = state();
+        if state.status != Status::Rx {
+            return false;
+        }
+        let len = data.len().min(255);
+        state.rx_frame[..len].copy_from_slice(&data[..len]);
+        state.rx_len = len;
+        let t = state.time_ms;
+        state.raise(Irq::RxDone { rssi, snr }, Event::DIO0(t));
+        true
+    }
+
+    // ends a single reception without a frame, as the SX127x does on DIO1
+    pub fn expire_rx(&mut self) -> bool {
+        let mut state = state();
+        if state.status != Status::Rx {
+            return false;
+        }
+        let t = state.time_ms;
+        state.raise(Irq::RxTimeout, Event::DIO1(t));
+        true
+    }
+
+    // outcome of the next channel activity detection
+    pub fn set_channel_activity(&mut self, detected: bool) {
+        state().channel_activity = detected;
+    }
+
+    // RSSI seen by carrier sense on any frequency
+    pub fn set_channel_rssi(&mut self, dbm: i16) {
+        state().channel_rssi = dbm;
+    }
+}
+
+pub(crate) fn radio() -> Radio_t {
+    // drivers are free to leave slots empty, so start from all None
+    let mut radio: Radio_t = unsafe { core::mem::zeroed() };
+    radio.Init = Some(init);
+    radio.SetChannel = Some(set_channel);
+    radio.SetRxConfig = Some(set_rx_config);
+    radio.SetTxConfig = Some(set_tx_config);
+    radio.Send = Some(send);
+    radio.Sleep = Some(sleep);
+    radio.Standby = Some(sleep);
+    radio.Rx = Some(rx);
+    radio.StartCad = Some(start_cad);
+    radio.Random = Some(random);
+    radio.GetStatus = Some(get_status);
+    radio.IsChannelFree = Some(is_channel_free);
+    radio.TimeOnAir = Some(time_on_air);
+    radio
+}
+
+extern "C" fn timer_start(_context: *mut c_void, _timer_id: u8, _timeout_ms: u32) {}
+
+extern "C" fn timer_stop(_context: *mut c_void, _timer_id: u8) {}
+
+extern "C" fn get_time_ms(_context: *mut c_void) -> u32 {
+    state().time_ms
+}
+
+extern "C" fn init(events: *mut RadioEvents_t) {
+    state().events = events;
+    let mut handlers: [IrqHandler; NUM_IRQ_HANDLES as usize] = [
+        Some(on_dio0),
+        Some(on_dio1),
+        None,
+        Some(on_dio3),
+        None,
+        None,
+    ];
+    unsafe { IoIrqInit(handlers.as_mut_ptr()) };
+}
+
+extern "C" fn set_channel(freq: u32) {
+    state().frequency = freq;
+}
+
+extern "C" fn set_rx_config(
+    _modem: RadioModems_t,
+    _bandwidth: u32,
+    _datarate: u32,
+    _coderate: u8,
+    _bandwidth_afc: u32,
+    _preamble_len: u16,
+    _symb_timeout: u16,
+    _fix_len: bool,
+    _payload_len: u8,
+    _crc_on: bool,
+    _freq_hop_on: bool,
+    _hop_period: u8,
+    _iq_inverted: bool,
+    rx_continuous: bool,
+) {
+    state().rx_continuous = rx_continuous;
+}
+
+extern "C" fn set_tx_config(
+    modem: RadioModems_t,
+    power: i8,
+    _fdev: u32,
+    bandwidth: u32,
+    datarate: u32,
+    coderate: u8,
+    preamble_len: u16,
+    fix_len: bool,
+    crc_on: bool,
+    _freq_hop_on: bool,
+    _hop_period: u8,
+    iq_inverted: bool,
+    _timeout: u32,
+) {
+    let mut state = state();
+    state.tx_config = (power, bandwidth, datarate, coderate, iq_inverted);
+    state.tx_packet = (modem, preamble_len, fix_len, crc_on);
+}
+
+// follows the last TX configuration, whatever modem is asked for
+extern "C" fn time_on_air(_modem: RadioModems_t, len: u8) -> u32 {
+    let state = state();
+    let (_, bandwidth, datarate, coderate, _) = state.tx_config;
+    let (modem, preamble_len, fix_len, crc_on) = state.tx_packet;
+    let us = if modem == RadioModems_t_MODEM_FSK {
+        FskAirtime {
+            datarate,
+            preamble_len,
+            // the drivers' default sync word
+            sync_word_len: 3,
+            fix_len,
+            crc_on,
+        }
+        .time_on_air_us(len)
+    } else {
+        let bandwidth = match bandwidth {
+            1 => LoRaBandwidth::_250KHZ,
+            2 => LoRaBandwidth::_500KHZ,
+            _ => LoRaBandwidth::_125KHZ,
+        };
+        let spreading_factor = match datarate {
+            6 => LoRaSpreadingFactor::_6,
+            8 => LoRaSpreadingFactor::_8,
+            9 => LoRaSpreadingFactor::_9,
+            10 => LoRaSpreadingFactor::_10,
+            11 => LoRaSpreadingFactor::_11,
+            12 => LoRaSpreadingFactor::_12,
+            _ => LoRaSpreadingFactor::_7,
+        };
+        let coding_rate = match coderate {
+            2 => LoRaCodingRate::_4_6,
+            3 => LoRaCodingRate::_4_7,
+            4 => LoRaCodingRate::_4_8,
+            _ => LoRaCodingRate::_4_5,
+        };
+        // same rule as the drivers
+        let low_data_rate_optimize = (bandwidth == LoRaBandwidth::_125KHZ && datarate >= 11)
+            || (bandwidth == LoRaBandwidth::_250KHZ && datarate == 12);
+        LoRaAirtime {
+            spreading_factor,
+            bandwidth,
+            coding_rate,
+            preamble_len,
+            implicit_header: fix_len,
+            crc_on,
+            low_data_rate_optimize,
+        }
+        .time_on_air_us(len)
+    };
+    (us + 999) / 1000
+}
+
+extern "C" fn send(buffer: *mut u8, size: u8) {
+    let mut state = state();
+    let data = unsafe { core::slice::from_raw_parts(buffer, size as usize) }.to_vec();
+    let (power, bandwidth, datarate, coderate, iq_inverted) = state.tx_config;
+    let frame = TxFrame {
+        data,
+        frequency: state.frequency,
+        power,
+        bandwidth,
+        datarate,
+        coderate,
+        iq_inverted,
+    };
+    state.tx_frames.push_back(frame);
+    state.status = Status::Tx;
+    let t = state.time_ms;
+    state.raise(Irq::TxDone, Event::DIO0(t));
+}
+
+extern "C" fn sleep() {
+    let mut state = state();
+    state.status = Status::Idle;
+    state.irqs.clear();
+    state.pending.clear();
+}
+
+extern "C" fn rx(timeout: u32) {
+    let mut state = state();
+    state.status = Status::Rx;
+    state.rx_deadline_ms = if timeout == 0 {
+        None
+    } else {
+        Some(state.time_ms.wrapping_add(timeout))
+    };
+}
+
+extern "C" fn start_cad() {
+    let mut state = state();
+    state.status = Status::Cad;
+    let t = state.time_ms;
+    state.raise(Irq::CadDone, Event::DIO3(t));
+}
+
+extern "C" fn get_status() -> RadioState_t {
+    match state().status {
+        Status::Idle => RadioState_t_RF_IDLE,
+        Status::Tx => RadioState_t_RF_TX_RUNNING,
+        Status::Rx => RadioState_t_RF_RX_RUNNING,
+        Status::Cad => RadioState_t_RF_CAD,
+    }
+}
+
+extern "C" fn is_channel_free(
+    _modem: RadioModems_t,
+    freq: u32,
+    rssi_thresh: i16,
+    _max_carrier_sense_time: u32,
+) -> bool {
+    let mut state = state();
+    state.frequency = freq;
+    state.channel_rssi <= rssi_thresh
+}
+
+// xorshift, so that tests see the same sequence on every run
+extern "C" fn random() -> u32 {
+    let mut state = state();
+    let mut x = state.rng;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    state.rng = x;
+    x
+}
+
+// reports the oldest raised interrupt, as long as it belongs to the DIO line
+// the Event was fed back on
+fn on_irq(routed_here: fn(&Irq) -> bool) {
+    let mut state = state();
+    match state.irqs.front() {
+        Some(irq) if routed_here(irq) => (),
+        _ => return,
+    }
+    let irq = state.irqs.pop_front().unwrap();
+    let events = unsafe { &*state.events };
+    unsafe {
+        match irq {
+            Irq::TxDone => {
+                state.status = Status::Idle;
+                if let Some(tx_done) = events.TxDone {
+                    tx_done()
+                }
+            }
+            Irq::RxDone { rssi, snr } => {
+                if !state.rx_continuous {
+                    state.status = Status::Idle;
+                }
+                let len = state.rx_len as u16;
+                if let Some(rx_done) = events.RxDone {
+                    rx_done(state.rx_frame.as_mut_ptr(), len, rssi, snr)
+                }
+            }
+            Irq::RxTimeout => {
+                state.status = Status::Idle;
+                if let Some(rx_timeout) = events.RxTimeout {
+                    rx_timeout()
+                }
+            }
+            Irq::CadDone => {
+                state.status = Status::Idle;
+                if let Some(cad_done) = events.CadDone {
+                    cad_done(state.channel_activity)
+                }
+            }
+        }
+    }
+}
+
+extern "C" fn on_dio0(_context: *mut c_void) {
+    on_irq(|irq| matches!(irq, Irq::TxDone | Irq::RxDone { .. }));
+}
+
+extern "C" fn on_dio1(_context: *mut c_void) {
+    on_irq(|irq| matches!(irq, Irq::RxTimeout));
+}
+
+extern "C" fn on_dio3(_context: *mut c_void) {
+    on_irq(|irq| matches!(irq, Irq::CadDone));
+}
@@ -0,0 +1,76 @@
+// Board wiring of an SX126x, handed to Radio::sx126x. The defaults describe
+// the Semtech SX1262MBXCAS shield the drivers were written for.
+use sx12xx_sys::Sx126xProfile_t;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sx126xProfile {
+    // None for a crystal
+    pub tcxo: Option<Tcxo>,
+    // DIO2 switches between TX and RX on its own; set_antenna_pins is called
+    // either way
+    pub dio2_rf_switch: bool,
+    pub regulator: Regulator,
+    pub pa: Sx126xPa,
+}
+
+impl Default for Sx126xProfile {
+    fn default() -> Self {
+        Sx126xProfile {
+            tcxo: Some(Tcxo {
+                voltage: TcxoVoltage::_1_7V,
+                wakeup_time_ms: 6,
+            }),
+            dio2_rf_switch: true,
+            regulator: Regulator::DcDc,
+            pa: Sx126xPa::Sx1262,
+        }
+    }
+}
+
+// a TCXO powered from DIO3
+#[derive(Debug, Clone, Copy)]
+pub struct Tcxo {
+    pub voltage: TcxoVoltage,
+    pub wakeup_time_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TcxoVoltage {
+    _1_6V = 0,
+    _1_7V = 1,
+    _1_8V = 2,
+    _2_2V = 3,
+    _2_4V = 4,
+    _2_7V = 5,
+    _3_0V = 6,
+    _3_3V = 7,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Regulator {
+    Ldo,
+    // needs the inductor fitted, but halves the current in TX and RX
+    DcDc,
+}
+
+// the SX1268 has the PA of the SX1262
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sx126xPa {
+    // low power, up to 15 dBm
+    Sx1261 = 1,
+    // high power, up to 22 dBm
+    Sx1262 = 2,
+}
+
+impl From<Sx126xProfile> for Sx126xProfile_t {
+    fn from(profile: Sx126xProfile) -> Sx126xProfile_t {
+        Sx126xProfile_t {
+            tcxo: profile.tcxo.is_some(),
+            tcxo_voltage: profile.tcxo.map_or(0, |tcxo| tcxo.voltage as u8),
+            tcxo_wakeup_time_ms: profile.tcxo.map_or(0, |tcxo| tcxo.wakeup_time_ms),
+            dio2_rf_switch: profile.dio2_rf_switch,
+            dcdc: profile.regulator == Regulator::DcDc,
+            device_id: profile.pa as u8,
+        }
+    }
+}
@@ -35,21 +35,35 @@ fn main() {
        .allowlist_type("Sx12xxEvent_t")
        .allowlist_type("Sx12xxState_t")
        .allowlist_type("AntPinsMode_t")
+       .allowlist_type("PaSelect_t")
        .allowlist_type("RadioModems_t")
+       .allowlist_type("RadioState_t")
        .allowlist_type("Sx12xx_t")
        .allowlist_type("Sx12xxRxMetadata_t")
+       .allowlist_type("Sx126xProfile_t")
+       .allowlist_type("RadioEvents_t")
+       .allowlist_type("IrqHandler")
+       .allowlist_var("NUM_IRQ_HANDLES")
        .rustified_enum("Sx12xxEvent_t")
        .rustified_enum("Sx12xxState_t")
        .rustified_enum("AntPinsMode_t")
+       .rustified_enum("PaSelect_t")
+       .allowlist_function("SX1272RadioNew")
        .allowlist_function("SX1276RadioNew")
        .allowlist_function("SX126xRadioNew")
        .allowlist_function("sx12xx_init")
+       .allowlist_function("sx12xx_recover")
+       .allowlist_function("IoIrqInit")
+       .allowlist_function("sx12xx_select")
        .allowlist_function("sx12xx_new_handle")
        .allowlist_function("sx12xx_handle_event")
        .allowlist_function("sx12xx_send")
        .allowlist_function("sx12xx_set_rx_buffer")
+       .allowlist_function("sx12xx_take_rx_buffer")
        .allowlist_function("sx12xx_get_rx_metadata")
        .allowlist_function("sx12xx_get_raw_buffer")
+       .allowlist_function("sx12xx_get_cad_activity_detected")
+       .allowlist_function("sx12xx_get_fhss_channel")
        .derive_copy(false)
        .derive_debug(false)
        .layout_tests(false)
@@ -60,10 +74,113 @@ fn main() {
     bindings
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
+
+    // typed register maps, see src/regs.rs
+    let mut regs = String::new();
+    for chip in &["sx1272", "sx1276"] {
+        regs.push_str(&format!("pub mod {} {{\n", chip));
+        for (module, header) in &[("lora", "LoRa"), ("fsk", "Fsk")] {
+            let path = format!("sx12xx/{}/{}Regs-{}.h", chip, chip, header);
+            let header = std::fs::read_to_string(&path).expect("Couldn't read register header!");
+            regs.push_str(&format!(
+                "pub mod {} {{\nuse super::super::{{Field, FieldValue, Register}};\n{}}}\n",
+                module,
+                register_map(&header)
+            ));
+        }
+        regs.push_str("}\n");
+    }
+    std::fs::write(out_path.join("regs.rs"), regs).expect("Couldn't write register maps!");
+}
+
+// Turns the #defines of a register header into constants, named as in the
+// header without the chip prefix:
+//   REG_* and REG_LR_*       Register
+//   *_MASK                   Field of the register whose name it extends;
+//                            its bits are those the mask clears
+//   values of such a field   FieldValue, if within the field
+//   anything else            u8
+fn register_map(header: &str) -> String {
+    let mut defines = Vec::new();
+    for line in header.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("#define") {
+            continue;
+        }
+        if let (Some(name), Some(value)) = (tokens.next(), tokens.next()) {
+            if let Some(hex) = value.strip_prefix("0x") {
+                let value = u8::from_str_radix(hex, 16).expect("Register value out of range!");
+                let name = name
+                    .trim_start_matches("SX1272_")
+                    .trim_start_matches("SX1276_");
+                defines.push((name.to_string(), value));
+            }
+        }
+    }
+    let registers: Vec<(&str, &str)> = defines
+        .iter()
+        .filter_map(|(name, _)| {
+            let short = name
+                .strip_prefix("REG_LR_")
+                .or_else(|| name.strip_prefix("REG_"))?;
+            Some((name.as_str(), short))
+        })
+        .collect();
+    // field name, register name, bits
+    let mut fields: Vec<(&str, &str, u8)> = Vec::new();
+    for (name, value) in &defines {
+        if let Some(field) = name.strip_suffix("_MASK") {
+            let short = field
+                .strip_prefix("RFLR_")
+                .or_else(|| field.strip_prefix("RF_"))
+                .unwrap_or(field);
+            let register = registers
+                .iter()
+                .filter(|(_, register)| {
+                    short == *register || short.starts_with(&format!("{}_", register))
+                })
+                .max_by_key(|(_, register)| register.len());
+            if let Some((register, _)) = register {
+                fields.push((field, register, !value));
+            }
+        }
+    }
+    let mut map = String::new();
+    for (name, value) in &defines {
+        let name = name.as_str();
+        if registers.iter().any(|(register, _)| *register == name) {
+            map.push_str(&format!(
+                "pub const {}: Register = Register({:#04X});\n",
+                name, value
+            ));
+        } else if let Some((_, register, bits)) = fields
+            .iter()
+            .find(|(field, _, _)| name.strip_suffix("_MASK") == Some(*field))
+        {
+            map.push_str(&format!(
+                "pub const {}: Field = Field {{ register: {}, bits: {:#04X} }};\n",
+                name, register, bits
+            ));
+        } else {
+            let field = fields
+                .iter()
+                .filter(|(field, _, bits)| {
+                    name.starts_with(&format!("{}_", field)) && value & !bits == 0
+                })
+                .max_by_key(|(field, _, _)| field.len());
+            match field {
+                Some((field, _, _)) => map.push_str(&format!(
+                    "pub const {}: FieldValue = FieldValue {{ field: {}_MASK, bits: {:#04X} }};\n",
+                    name, field, value
+                )),
+                None => map.push_str(&format!("pub const {}: u8 = {:#04X};\n", name, value)),
+            }
+        }
+    }
+    map
 }
 
 // #[cfg(not(workaround_build))]
 // fn main() {
 //   cargo_5730::run_build_script();
 // }
-
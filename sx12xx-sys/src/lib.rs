@@ -4,4 +4,6 @@
 #![allow(clippy::all)]
 #![cfg_attr(not(test), no_std)]
 
-include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
\ No newline at end of file
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+pub mod regs;
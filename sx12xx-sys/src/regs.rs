@@ -0,0 +1,43 @@
+// Register maps of the SX1272 and SX1276, generated by build.rs from the
+// sx127xRegs-LoRa.h and sx127xRegs-Fsk.h headers the drivers use. The
+// constants keep the names of the headers, without the chip prefix, e.g.
+// regs::sx1276::lora::REG_LR_OCP, or regs::sx1276::fsk::RF_OPMODE_MASK for the
+// Field that the C mask clears and RF_OPMODE_SLEEP for one of its values.
+
+// address of an SX127x register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register(pub u8);
+
+// bits of a register that hold one setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub register: Register,
+    pub bits: u8,
+}
+
+// a setting of a field, already shifted into place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldValue {
+    pub field: Field,
+    pub bits: u8,
+}
+
+impl From<Register> for u16 {
+    fn from(register: Register) -> u16 {
+        register.0 as u16
+    }
+}
+
+impl Field {
+    // the field's bits of a register value, left in place
+    pub fn get(self, value: u8) -> u8 {
+        value & self.bits
+    }
+
+    // a register value with the field replaced by bits
+    pub fn set(self, value: u8, bits: u8) -> u8 {
+        (value & !self.bits) | (bits & self.bits)
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/regs.rs"));
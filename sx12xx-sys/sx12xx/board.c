@@ -1,12 +1,13 @@
 #include "board.h"
 #include "sx12xx.h"
 
-extern Sx12xx_t sx12xx_handle;
+extern Sx12xx_t * sx12xx_handle;
 
 void
 DelayMs(uint32_t ms)
 {
-    (*sx12xx_handle.bindings.delay_ms)(ms);
+    (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, ms);
+    sx12xx_handle->delay_time_ms += ms;
 }
 
 void
@@ -24,14 +25,14 @@ memcpy1(uint8_t * dst, const uint8_t * src, uint16_t size)
 uint16_t
 SpiInOut(LF_Spi_t * obj, uint16_t outData)
 {
-    uint16_t ret = (*sx12xx_handle.bindings.spi_in_out)((uint8_t)outData);
+    uint16_t ret = (*sx12xx_handle->bindings.spi_in_out)(sx12xx_handle->bindings.context, (uint8_t)outData);
     return ret;
 }
 
 void
 GpioWrite(LF_Gpio_t * obj, uint32_t value)
 {
-    (*sx12xx_handle.bindings.spi_nss)((bool)value);
+    (*sx12xx_handle->bindings.spi_nss)(sx12xx_handle->bindings.context, (bool)value);
 }
 
 uint32_t
@@ -40,44 +41,118 @@ GpioRead(LF_Gpio_t * obj)
     return 0;
 }
 
+// returns the index of a timer registered through TimerInit, or -1
+static int8_t
+TimerGetId(TimerEvent_t * obj)
+{
+    for (uint8_t i = 0; i < sx12xx_handle->num_timers; i++)
+    {
+        if (sx12xx_handle->timers[i] == obj)
+        {
+            return i;
+        }
+    }
+    return -1;
+}
+
 void
 TimerInit(TimerEvent_t * obj, void (*callback)(void *))
 {
+    obj->Timestamp   = 0;
+    obj->ReloadValue = 0;
+    obj->IsRunning   = false;
+    obj->Callback    = callback;
+    obj->Next        = NULL;
+
+    if (TimerGetId(obj) < 0 && sx12xx_handle->num_timers < NUM_TIMERS)
+    {
+        sx12xx_handle->timers[sx12xx_handle->num_timers++] = obj;
+    }
 }
 void
 TimerIrqHandler(void)
 {
 }
 void
+TimerIrqDispatch(uint8_t timer_id)
+{
+    if (timer_id >= sx12xx_handle->num_timers)
+    {
+        return;
+    }
+
+    TimerEvent_t * obj = sx12xx_handle->timers[timer_id];
+    // a stale expiry for a timer that was since stopped is dropped
+    if (obj->IsRunning)
+    {
+        obj->IsRunning = false;
+        if (obj->Callback != NULL)
+        {
+            (*obj->Callback)(NULL);
+        }
+    }
+}
+void
 TimerStart(TimerEvent_t * obj)
 {
+    int8_t id = TimerGetId(obj);
+
+    obj->Timestamp = TimerGetCurrentTime();
+    // the drivers start their timeouts unconditionally; 0 means none
+    if (obj->ReloadValue == 0)
+    {
+        obj->IsRunning = false;
+        return;
+    }
+    obj->IsRunning = true;
+    if (id >= 0 && sx12xx_handle->bindings.timer_start != NULL)
+    {
+        (*sx12xx_handle->bindings.timer_start)(sx12xx_handle->bindings.context, id, obj->ReloadValue);
+    }
 }
 void
 TimerStop(TimerEvent_t * obj)
 {
+    int8_t id = TimerGetId(obj);
+
+    obj->IsRunning = false;
+    if (id >= 0 && sx12xx_handle->bindings.timer_stop != NULL)
+    {
+        (*sx12xx_handle->bindings.timer_stop)(sx12xx_handle->bindings.context, id);
+    }
 }
 void
 TimerReset(TimerEvent_t * obj)
 {
+    TimerStop(obj);
+    TimerStart(obj);
 }
 void
 TimerSetValue(TimerEvent_t * obj, uint32_t value)
 {
+    TimerStop(obj);
+    obj->ReloadValue = value;
 }
 TimerTime_t
 TimerGetCurrentTime(void)
 {
-    return 0;
+    if (sx12xx_handle->bindings.get_time_ms != NULL)
+    {
+        return (*sx12xx_handle->bindings.get_time_ms)(sx12xx_handle->bindings.context);
+    }
+    // without a clock, busy-wait loops that also call DelayMs still end
+    return sx12xx_handle->delay_time_ms;
 }
 TimerTime_t
 TimerGetElapsedTime(TimerTime_t savedTime)
 {
-    return 0;
+    // wrapping arithmetic handles the counter rolling over
+    return TimerGetCurrentTime() - savedTime;
 }
 TimerTime_t
 TimerGetFutureTime(TimerTime_t eventInFuture)
 {
-    return 0;
+    return TimerGetCurrentTime() + eventInFuture;
 }
 void
 TimerLowPowerHandler(void)
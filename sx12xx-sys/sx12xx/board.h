@@ -12,6 +12,7 @@ extern "C"
 #include <string.h>
 
 #define NUM_IRQ_HANDLES 6
+#define NUM_TIMERS 3
 
     /*!
      * Hardware IO IRQ callback function definition
@@ -46,6 +47,14 @@ extern "C"
         _AntModeMax = 0xFFFFFFFF // force 32-bit value
     } AntPinsMode_t;
 
+    typedef enum PaSelect_t
+    {
+        PaSelectAuto,    // the drivers' default: PA_BOOST on every band (SX1276)
+        PaSelectRfo,     // board only routes the RFO pin to the antenna
+        PaSelectPaBoost, // board only routes the PA_BOOST pin to the antenna
+        _PaSelectMax = 0xFFFFFFFF // force 32-bit value
+    } PaSelect_t;
+
     typedef void(IrqHandler)(void *);
     typedef void(GpioIrqHandler)(void *);
 
@@ -57,7 +66,7 @@ extern "C"
         uint32_t Timestamp;         //! Current timer value
         uint32_t ReloadValue;       //! Timer delay value
         bool     IsRunning;         //! Is the timer currently running
-        void (*Callback)(void);     //! Timer IRQ callback function
+        void (*Callback)(void *);   //! Timer IRQ callback function
         struct TimerEvent_s * Next; //! Pointer to the next Timer object.
     } TimerEvent_t;
 
@@ -69,6 +78,7 @@ extern "C"
 
     void        TimerInit(TimerEvent_t * obj, void (*callback)(void * context));
     void        TimerIrqHandler(void);
+    void        TimerIrqDispatch(uint8_t timer_id);
     void        TimerStart(TimerEvent_t * obj);
     void        TimerStop(TimerEvent_t * obj);
     void        TimerReset(TimerEvent_t * obj);
@@ -93,21 +103,34 @@ extern "C"
 
     typedef struct
     {
+        // handed back as the first argument of every callback below so that
+        // one set of callbacks can serve several radios
+        void * context;
         // must provide for drivers to work
-        uint8_t (*spi_in_out)(uint8_t outData);
-        void (*spi_nss)(bool sel);
-        void (*reset)(bool enable);
-        void (*delay_ms)(uint32_t);
-        bool (*busy_pin_status)(); // only necessary for SX126x
+        uint8_t (*spi_in_out)(void * context, uint8_t outData);
+        void (*spi_nss)(void * context, bool sel);
+        void (*reset)(void * context, bool enable);
+        void (*delay_ms)(void * context, uint32_t);
+        bool (*busy_pin_status)(void * context); // only necessary for SX126x
         // the rest of these functions are optional for board control
         // if external PA, this callback allows you to reduce power
-        // of SX12xx and to do any PA switching
-        uint8_t (*reduce_power)(uint8_t);
+        // of SX12xx and to do any PA switching; given the requested power
+        // in dBm, which may be negative, it returns the dB to take off
+        uint8_t (*reduce_power)(void * context, int8_t);
+        // which PA output the board has wired up (SX127x only)
+        PaSelect_t pa_select;
         uint8_t (*set_board_tcxo)(
-            bool enable); // to control power supply TCXO (wake/sleep)
+            void * context,
+            bool   enable); // to control power supply TCXO (wake/sleep)
         void (*set_antenna_pins)(
+            void *        context,
             AntPinsMode_t mode,
             uint8_t       power); // to control antenna pins for TX/RX/Sleep
+        // timers used by the drivers for TX/RX timeouts; timer_id is in
+        // [0, NUM_TIMERS) and expiry must be fed back as Sx12xxEvent_Timer1..3
+        void (*timer_start)(void * context, uint8_t timer_id, uint32_t timeout_ms);
+        void (*timer_stop)(void * context, uint8_t timer_id);
+        uint32_t (*get_time_ms)(void * context); // free-running millisecond counter
 
     } BoardBindings_t;
 
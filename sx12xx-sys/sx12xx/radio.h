@@ -379,6 +379,34 @@ struct Radio_s
      * \param [in]  bool        To enable or disable Tcxo
      */
     void ( *EnableTcxo )( void );
+    /*!
+     * \brief Sets the FSK sync word
+     *
+     * \remark Applies to FSK modem only
+     *
+     * \param [IN] syncWord   Sync word bytes, sent first byte first
+     * \param [IN] size       Number of sync word bytes [0: sync word off, 1..8]
+     */
+    void ( *SetSyncWord )( uint8_t *syncWord, uint8_t size );
+    /*!
+     * \brief Enables or disables the FSK data whitening
+     *
+     * \remark Applies to FSK modem only
+     *
+     * \param [IN] enable     Enables the whitening
+     */
+    void ( *SetWhitening )( bool enable );
+    /*!
+     * \brief Sets the radio in infinite preamble transmission mode, using the
+     *        modulation of the last configuration
+     *
+     * \remark Available on SX126x radios only.
+     *
+     * \param [IN]: freq       Channel RF frequency
+     * \param [IN]: power      Sets the output power [dBm]
+     * \param [IN]: time       Transmission mode timeout [s]
+     */
+    void ( *SetTxInfinitePreamble )( uint32_t freq, int8_t power, uint16_t time );
 
 };
 
@@ -226,6 +226,15 @@ void SX126xRadioStartCad( void );
  */
 void SX126xRadioSetTxContinuousWave( uint32_t freq, int8_t power, uint16_t time );
 
+/*!
+ * \brief Sets the radio in infinite preamble transmission mode
+ *
+ * \param [IN]: freq       Channel RF frequency
+ * \param [IN]: power      Sets the output power [dBm]
+ * \param [IN]: time       Transmission mode timeout [s]
+ */
+void SX126xRadioSetTxInfinitePreamble( uint32_t freq, int8_t power, uint16_t time );
+
 /*!
  * \brief Reads the current RSSI value
  *
@@ -291,6 +300,21 @@ void SX126xRadioSetPublicNetwork( bool enable );
  */
 uint32_t SX126xRadioGetWakeupTime( void );
 
+/*!
+ * \brief Sets the FSK sync word, applied by the next Rx/Tx configuration
+ *
+ * \param [IN] syncWord   Sync word bytes, sent first byte first
+ * \param [IN] size       Number of sync word bytes [0: sync word off, 1..8]
+ */
+void SX126xRadioSetSyncWord( uint8_t *syncWord, uint8_t size );
+
+/*!
+ * \brief Enables the FSK whitening, applied by the next Rx/Tx configuration
+ *
+ * \param [IN] enable     Enables the whitening
+ */
+void SX126xRadioSetWhitening( bool enable );
+
 /*!
  * \brief Process radio irq
  */
@@ -346,6 +370,9 @@ Radio_t SX126xRadioNew(){
         SX126xRadioRxBoosted,
         SX126xRadioSetRxDutyCycle,
         NULL, // void (*EnableTcxo)( void );
+        SX126xRadioSetSyncWord,
+        SX126xRadioSetWhitening,
+        SX126xRadioSetTxInfinitePreamble,
 
     };
     return radio;
@@ -414,6 +441,13 @@ uint8_t SX126xRadioRxPayload[255];
 
 bool IrqFired = false;
 
+/*!
+ * FSK sync word and whitening used by the Rx/Tx configurations
+ */
+uint8_t SX126xFskSyncWord[8] = { 0xC1, 0x94, 0xC1, 0x00, 0x00, 0x00, 0x00, 0x00 };
+uint8_t SX126xFskSyncWordSize = 3;
+bool SX126xFskWhitening = true;
+
 /*
  * SX126x DIO IRQ callback functions prototype
  */
@@ -501,7 +535,7 @@ void SX126xRadioInit( RadioEvents_t *events )
 
     SX126xInit( SX126xRadioOnDioIrq );
     SX126xSetStandby( STDBY_RC );
-    SX126xSetRegulatorMode( USE_DCDC );
+    SX126xSetRegulatorMode( ( RadioRegulatorMode_t )SX126xGetBoardRegulatorMode( ) );
 
     SX126xSetBufferBaseAddress( 0x00, 0x00 );
     SX126xSetTxParams( 0, RADIO_RAMP_200_US );
@@ -588,6 +622,7 @@ bool SX126xRadioIsChannelFree( RadioModems_t modem, uint32_t freq, int16_t rssiT
             status = false;
             break;
         }
+        DelayMs( 1 );
     }
     SX126xRadioSleep( );
     return status;
@@ -655,7 +690,7 @@ void SX126xRadioSetRxConfig( RadioModems_t modem, uint32_t bandwidth,
             SX126x.PacketParams.PacketType = PACKET_TYPE_GFSK;
             SX126x.PacketParams.Params.Gfsk.PreambleLength = ( preambleLen << 3 ); // convert byte into bit
             SX126x.PacketParams.Params.Gfsk.PreambleMinDetect = RADIO_PREAMBLE_DETECTOR_08_BITS;
-            SX126x.PacketParams.Params.Gfsk.SyncWordLength = 3 << 3; // convert byte into bit
+            SX126x.PacketParams.Params.Gfsk.SyncWordLength = SX126xFskSyncWordSize << 3; // convert byte into bit
             SX126x.PacketParams.Params.Gfsk.AddrComp = RADIO_ADDRESSCOMP_FILT_OFF;
             SX126x.PacketParams.Params.Gfsk.HeaderType = ( fixLen == true ) ? RADIO_PACKET_FIXED_LENGTH : RADIO_PACKET_VARIABLE_LENGTH;
             SX126x.PacketParams.Params.Gfsk.PayloadLength = MaxPayloadLength;
@@ -667,13 +702,13 @@ void SX126xRadioSetRxConfig( RadioModems_t modem, uint32_t bandwidth,
             {
                 SX126x.PacketParams.Params.Gfsk.CrcLength = SX126x_RADIO_CRC_OFF;
             }
-            SX126x.PacketParams.Params.Gfsk.DcFree = RADIO_DC_FREEWHITENING;
+            SX126x.PacketParams.Params.Gfsk.DcFree = ( SX126xFskWhitening == true ) ? RADIO_DC_FREEWHITENING : RADIO_DC_FREE_OFF;
 
             SX126xRadioStandby( );
             SX126xRadioSetModem( ( SX126x.ModulationParams.PacketType == PACKET_TYPE_GFSK ) ? MODEM_FSK : MODEM_LORA );
             SX126xSetModulationParams( &SX126x.ModulationParams );
             SX126xSetPacketParams( &SX126x.PacketParams );
-            SX126xSetSyncWord( ( uint8_t[] ){ 0xC1, 0x94, 0xC1, 0x00, 0x00, 0x00, 0x00, 0x00 } );
+            SX126xSetSyncWord( SX126xFskSyncWord );
             SX126xSetWhiteningSeed( 0x01FF );
 
             RxTimeout = ( uint32_t )( symbTimeout * ( ( 1.0 / ( double )datarate ) * 8.0 ) * 1000 );
@@ -768,7 +803,7 @@ void SX126xRadioSetTxConfig( RadioModems_t modem, int8_t power, uint32_t fdev,
             SX126x.PacketParams.PacketType = PACKET_TYPE_GFSK;
             SX126x.PacketParams.Params.Gfsk.PreambleLength = ( preambleLen << 3 ); // convert byte into bit
             SX126x.PacketParams.Params.Gfsk.PreambleMinDetect = RADIO_PREAMBLE_DETECTOR_08_BITS;
-            SX126x.PacketParams.Params.Gfsk.SyncWordLength = 3 << 3 ; // convert byte into bit
+            SX126x.PacketParams.Params.Gfsk.SyncWordLength = SX126xFskSyncWordSize << 3; // convert byte into bit
             SX126x.PacketParams.Params.Gfsk.AddrComp = RADIO_ADDRESSCOMP_FILT_OFF;
             SX126x.PacketParams.Params.Gfsk.HeaderType = ( fixLen == true ) ? RADIO_PACKET_FIXED_LENGTH : RADIO_PACKET_VARIABLE_LENGTH;
 
@@ -780,13 +815,13 @@ void SX126xRadioSetTxConfig( RadioModems_t modem, int8_t power, uint32_t fdev,
             {
                 SX126x.PacketParams.Params.Gfsk.CrcLength = SX126x_RADIO_CRC_OFF;
             }
-            SX126x.PacketParams.Params.Gfsk.DcFree = RADIO_DC_FREEWHITENING;
+            SX126x.PacketParams.Params.Gfsk.DcFree = ( SX126xFskWhitening == true ) ? RADIO_DC_FREEWHITENING : RADIO_DC_FREE_OFF;
 
             SX126xRadioStandby( );
             SX126xRadioSetModem( ( SX126x.ModulationParams.PacketType == PACKET_TYPE_GFSK ) ? MODEM_FSK : MODEM_LORA );
             SX126xSetModulationParams( &SX126x.ModulationParams );
             SX126xSetPacketParams( &SX126x.PacketParams );
-            SX126xSetSyncWord( ( uint8_t[] ){ 0xC1, 0x94, 0xC1, 0x00, 0x00, 0x00, 0x00, 0x00 } );
+            SX126xSetSyncWord( SX126xFskSyncWord );
             SX126xSetWhiteningSeed( 0x01FF );
             break;
 
@@ -865,43 +900,70 @@ uint32_t SX126xRadioTimeOnAir( RadioModems_t modem, uint8_t pktLen )
 {
     uint32_t airTime = 0;
 
-    // switch( modem )
-    // {
-    // case MODEM_FSK:
-    //     {
-    //        airTime = rint( ( 8 * ( SX126x.PacketParams.Params.Gfsk.PreambleLength +
-    //                                  ( SX126x.PacketParams.Params.Gfsk.SyncWordLength >> 3 ) +
-    //                                  ( ( SX126x.PacketParams.Params.Gfsk.HeaderType == RADIO_PACKET_FIXED_LENGTH ) ? 0.0 : 1.0 ) +
-    //                                  pktLen +
-    //                                  ( ( SX126x.PacketParams.Params.Gfsk.CrcLength == SX126x_RADIO_CRC_2_BYTES ) ? 2.0 : 0 ) ) /
-    //                                  SX126x.ModulationParams.Params.Gfsk.BitRate ) * 1e3 );
-    //     }
-    //     break;
-    // case MODEM_LORA:
-    //     {
-    //         double ts = SX126xRadioLoRaSymbTime[SX126x.ModulationParams.Params.LoRa.Bandwidth - 4][12 - SX126x.ModulationParams.Params.LoRa.SpreadingFactor];
-    //         // time of preamble
-    //         double tPreamble = ( SX126x.PacketParams.Params.LoRa.PreambleLength + 4.25 ) * ts;
-    //         // Symbol length of payload and time
-    //         double tmp = ceil( ( 8 * pktLen - 4 * SX126x.ModulationParams.Params.LoRa.SpreadingFactor +
-    //                              28 + 16 * SX126x.PacketParams.Params.LoRa.CrcMode -
-    //                              ( ( SX126x.PacketParams.Params.LoRa.HeaderType == LORA_PACKET_FIXED_LENGTH ) ? 20 : 0 ) ) /
-    //                              ( double )( 4 * ( SX126x.ModulationParams.Params.LoRa.SpreadingFactor -
-    //                              ( ( SX126x.ModulationParams.Params.LoRa.LowDatarateOptimize > 0 ) ? 2 : 0 ) ) ) ) *
-    //                              ( ( SX126x.ModulationParams.Params.LoRa.CodingRate % 4 ) + 4 );
-    //         double nPayload = 8 + ( ( tmp > 0 ) ? tmp : 0 );
-    //         double tPayload = nPayload * ts;
-    //         // Time on air
-    //         double tOnAir = tPreamble + tPayload;
-    //         // return milli seconds
-    //         airTime = floor( tOnAir + 0.999 );
-    //     }
-    //     break;
-    // default:
-    // break;
-    // }
-    return airTime;
+    switch( modem )
+    {
+    case MODEM_FSK:
+        {
+            RadioCrcTypes_t crcLength = SX126x.PacketParams.Params.Gfsk.CrcLength;
+            double crcBytes = 2.0;
+
+            if( crcLength == SX126x_RADIO_CRC_OFF )
+            {
+                crcBytes = 0.0;
+            }
+            else if( ( crcLength == SX126x_RADIO_CRC_1_BYTES ) || ( crcLength == SX126x_RADIO_CRC_1_BYTES_INV ) )
+            {
+                crcBytes = 1.0;
+            }
+            // Preamble and sync word lengths are kept in bits
+            airTime = round( ( 8 * ( ( SX126x.PacketParams.Params.Gfsk.PreambleLength >> 3 ) +
+                                     ( SX126x.PacketParams.Params.Gfsk.SyncWordLength >> 3 ) +
+                                     ( ( SX126x.PacketParams.Params.Gfsk.HeaderType == RADIO_PACKET_FIXED_LENGTH ) ? 0.0 : 1.0 ) +
+                                     pktLen +
+                                     crcBytes ) /
+                                     SX126x.ModulationParams.Params.Gfsk.BitRate ) * 1e3 );
+        }
+        break;
+    case MODEM_LORA:
+        {
+            double bw = 0.0;
 
+            switch( SX126x.ModulationParams.Params.LoRa.Bandwidth )
+            {
+            case LORA_BW_250:
+                bw = 250000;
+                break;
+            case LORA_BW_500:
+                bw = 500000;
+                break;
+            default:
+                bw = 125000;
+                break;
+            }
+
+            // Symbol time (ms)
+            double ts = ( 1 << SX126x.ModulationParams.Params.LoRa.SpreadingFactor ) * 1e3 / bw;
+            // time of preamble
+            double tPreamble = ( SX126x.PacketParams.Params.LoRa.PreambleLength + 4.25 ) * ts;
+            // Symbol length of payload and time
+            double tmp = ceil( ( 8 * pktLen - 4 * SX126x.ModulationParams.Params.LoRa.SpreadingFactor +
+                                 28 + 16 * SX126x.PacketParams.Params.LoRa.CrcMode -
+                                 ( ( SX126x.PacketParams.Params.LoRa.HeaderType == LORA_PACKET_FIXED_LENGTH ) ? 20 : 0 ) ) /
+                                 ( double )( 4 * ( SX126x.ModulationParams.Params.LoRa.SpreadingFactor -
+                                 ( ( SX126x.ModulationParams.Params.LoRa.LowDatarateOptimize > 0 ) ? 2 : 0 ) ) ) ) *
+                                 ( SX126x.ModulationParams.Params.LoRa.CodingRate + 4 );
+            double nPayload = 8 + ( ( tmp > 0 ) ? tmp : 0 );
+            double tPayload = nPayload * ts;
+            // Time on air
+            double tOnAir = tPreamble + tPayload;
+            // return milli seconds
+            airTime = floor( tOnAir + 0.999 );
+        }
+        break;
+    default:
+        break;
+    }
+    return airTime;
 }
 
 void SX126xRadioSend( uint8_t *buffer, uint8_t size )
@@ -930,6 +992,9 @@ void SX126xRadioSleep( void )
 {
     SleepParams_t params = { .Value = 0 };
 
+    TimerStop( &SX126xTxTimeoutTimer );
+    TimerStop( &SX126xRxTimeoutTimer );
+
     params.Fields.WarmStart = 1;
     SX126xSetSleep( params );
 
@@ -938,6 +1003,9 @@ void SX126xRadioSleep( void )
 
 void SX126xRadioStandby( void )
 {
+    TimerStop( &SX126xTxTimeoutTimer );
+    TimerStop( &SX126xRxTimeoutTimer );
+
     SX126xSetStandby( STDBY_RC );
 }
 
@@ -989,11 +1057,21 @@ void SX126xRadioRxBoosted( uint32_t timeout )
 
 void SX126xRadioSetRxDutyCycle( uint32_t rxTime, uint32_t sleepTime )
 {
+    // the last Send may have left only the Tx interrupts enabled
+    SX126xSetDioIrqParams( IRQ_RADIO_ALL,
+                           IRQ_RADIO_ALL,
+                           IRQ_RADIO_NONE,
+                           IRQ_RADIO_NONE );
+
     SX126xSetRxDutyCycle( rxTime, sleepTime );
 }
 
 void SX126xRadioStartCad( void )
 {
+    SX126xSetDioIrqParams( IRQ_CAD_DONE | IRQ_CAD_ACTIVITY_DETECTED,
+                           IRQ_CAD_DONE | IRQ_CAD_ACTIVITY_DETECTED,
+                           IRQ_RADIO_NONE,
+                           IRQ_RADIO_NONE );
     SX126xSetCad( );
 }
 
@@ -1012,6 +1090,16 @@ void SX126xRadioSetTxContinuousWave( uint32_t freq, int8_t power, uint16_t time
     TimerStart( &SX126xTxTimeoutTimer );
 }
 
+void SX126xRadioSetTxInfinitePreamble( uint32_t freq, int8_t power, uint16_t time )
+{
+    SX126xSetRfFrequency( freq );
+    SX126xSetRfTxPower( power );
+    SX126xSetTxInfinitePreamble( );
+
+    TimerSetValue( &SX126xTxTimeoutTimer, time * 1e3 );
+    TimerStart( &SX126xTxTimeoutTimer );
+}
+
 int16_t SX126xRadioRssi( RadioModems_t modem )
 {
     return SX126xGetRssiInst( );
@@ -1088,6 +1176,30 @@ uint32_t SX126xRadioGetWakeupTime( void )
     return SX126xGetBoardTcxoWakeupTime( ) + SX126x_RADIO_WAKEUP_TIME;
 }
 
+/*!
+ * \brief Sets the FSK sync word, applied by the next Rx/Tx configuration
+ *
+ * \remark Sync words longer than 8 bytes are truncated
+ */
+void SX126xRadioSetSyncWord( uint8_t *syncWord, uint8_t size )
+{
+    if( size > 8 )
+    {
+        size = 8;
+    }
+    memset( SX126xFskSyncWord, 0, sizeof( SX126xFskSyncWord ) );
+    memcpy1( SX126xFskSyncWord, syncWord, size );
+    SX126xFskSyncWordSize = size;
+}
+
+/*!
+ * \brief Enables the FSK whitening, applied by the next Rx/Tx configuration
+ */
+void SX126xRadioSetWhitening( bool enable )
+{
+    SX126xFskWhitening = enable;
+}
+
 void SX126xRadioOnTxTimeoutIrq( void* context )
 {
     if( ( SX126xRadioEvents != NULL ) && ( SX126xRadioEvents->TxTimeout != NULL ) )
@@ -1154,7 +1266,15 @@ void SX126xRadioIrqProcess( void )
             SX126xGetPacketStatus( &SX126xRadioPktStatus );
             if( ( SX126xRadioEvents != NULL ) && ( SX126xRadioEvents->RxDone != NULL ) )
             {
-                SX126xRadioEvents->RxDone( SX126xRadioRxPayload, size, SX126xRadioPktStatus.Params.LoRa.RssiPkt, SX126xRadioPktStatus.Params.LoRa.SnrPkt );
+                if( SX126xRadioPktStatus.packetType == PACKET_TYPE_GFSK )
+                {
+                    // FSK has no SNR
+                    SX126xRadioEvents->RxDone( SX126xRadioRxPayload, size, SX126xRadioPktStatus.Params.Gfsk.RssiAvg, 0 );
+                }
+                else
+                {
+                    SX126xRadioEvents->RxDone( SX126xRadioRxPayload, size, SX126xRadioPktStatus.Params.LoRa.RssiPkt, SX126xRadioPktStatus.Params.LoRa.SnrPkt );
+                }
             }
         }
 
@@ -26,7 +26,7 @@
 /*!
  * Radio hardware and global parameters
  */
-extern Sx12xx_t sx12xx_handle;
+extern Sx12xx_t * sx12xx_handle;
 extern SX126x_t SX126x;
 
 /*!
@@ -52,7 +52,9 @@ void SX126xIoInit( void )
 
 void SX126xIoIrqInit( DioIrqHandler dioIrq )
 {
-    IoIrqInit(&dioIrq);
+    // all IRQs are routed to DIO1 and share one handler
+    DioIrqHandler *irqHandlers[NUM_IRQ_HANDLES] = { dioIrq };
+    IoIrqInit( irqHandlers );
 }
 
 void SX126xIoDeInit( void )
@@ -67,31 +69,66 @@ void SX126xIoDbgInit( void )
 void SX126xIoTcxoInit( void )
 {
     CalibrationParams_t calibParam;
+    Sx126xProfile_t *profile = &sx12xx_handle->sx126x_profile;
 
-    SX126xSetDio3AsTcxoCtrl( TCXO_CTRL_1_7V, SX126xGetBoardTcxoWakeupTime( ) << 6 ); // convert from ms to SX126x time base
+    if( profile->tcxo == true )
+    {
+        SX126xSetDio3AsTcxoCtrl( ( RadioTcxoCtrlVoltage_t )profile->tcxo_voltage, SX126xGetBoardTcxoWakeupTime( ) << 6 ); // convert from ms to SX126x time base
+    }
     calibParam.Value = 0x7F;
     SX126xCalibrate( calibParam );
 
-    SX126xSetDio2AsRfSwitchCtrl(true);
+    SX126xSetDio2AsRfSwitchCtrl( profile->dio2_rf_switch );
 }
 
 uint32_t SX126xGetBoardTcxoWakeupTime( void )
 {
-    return 6;
+    if( sx12xx_handle->sx126x_profile.tcxo == false )
+    {
+        return 0;
+    }
+    return sx12xx_handle->sx126x_profile.tcxo_wakeup_time_ms;
+}
+
+uint8_t SX126xGetBoardRegulatorMode( void )
+{
+    return ( sx12xx_handle->sx126x_profile.dcdc == true ) ? USE_DCDC : USE_LDO;
 }
 
 void SX126xReset( void )
 {
     // reset required, even after TCXO enabling routine
-    (*sx12xx_handle.bindings.reset)(true);
-    (*sx12xx_handle.bindings.delay_ms)(20);
-    (*sx12xx_handle.bindings.reset)(false);
-    (*sx12xx_handle.bindings.delay_ms)(10);
+    (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, true);
+    (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, 20);
+    (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, false);
+    (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, 10);
 }
 
 void SX126xWaitOnBusy( void )
 {
-    while( (*sx12xx_handle.bindings.busy_pin_status)() == 1);
+    if( sx12xx_handle->busy_timeout == true )
+    {
+        return;
+    }
+    for( uint16_t i = 0; i < SX126X_BUSY_SPIN_COUNT; i++ )
+    {
+        if( (*sx12xx_handle->bindings.busy_pin_status)(sx12xx_handle->bindings.context) == 0 )
+        {
+            return;
+        }
+    }
+    // DelayMs keeps the time moving on boards without a clock
+    TimerTime_t start = TimerGetCurrentTime( );
+    while( (*sx12xx_handle->bindings.busy_pin_status)(sx12xx_handle->bindings.context) == 1 )
+    {
+        if( TimerGetElapsedTime( start ) >= SX126X_BUSY_TIMEOUT_MS )
+        {
+            // disconnected or browned out
+            sx12xx_handle->busy_timeout = true;
+            return;
+        }
+        DelayMs( 1 );
+    }
 }
 
 void SX126xWakeup( void )
@@ -110,6 +147,10 @@ void SX126xWakeup( void )
 void SX126xWriteCommand( RadioCommands_t command, uint8_t *buffer, uint16_t size )
 {
     SX126xCheckDeviceReady( );
+    if( sx12xx_handle->busy_timeout == true )
+    {
+        return;
+    }
 
     GpioWrite( &SX126x.Spi.Nss, 0 );
 
@@ -133,6 +174,10 @@ uint8_t SX126xReadCommand( RadioCommands_t command, uint8_t *buffer, uint16_t si
     uint8_t status = 0;
 
     SX126xCheckDeviceReady( );
+    if( sx12xx_handle->busy_timeout == true )
+    {
+        return status;
+    }
 
     GpioWrite( &SX126x.Spi.Nss, 0 );
 
@@ -153,6 +198,10 @@ uint8_t SX126xReadCommand( RadioCommands_t command, uint8_t *buffer, uint16_t si
 void SX126xWriteRegisters( uint16_t address, uint8_t *buffer, uint16_t size )
 {
     SX126xCheckDeviceReady( );
+    if( sx12xx_handle->busy_timeout == true )
+    {
+        return;
+    }
 
     GpioWrite( &SX126x.Spi.Nss, 0 );
     
@@ -178,6 +227,10 @@ void SX126xWriteRegister( uint16_t address, uint8_t value )
 void SX126xReadRegisters( uint16_t address, uint8_t *buffer, uint16_t size )
 {
     SX126xCheckDeviceReady( );
+    if( sx12xx_handle->busy_timeout == true )
+    {
+        return;
+    }
 
     GpioWrite( &SX126x.Spi.Nss, 0 );
 
@@ -196,7 +249,7 @@ void SX126xReadRegisters( uint16_t address, uint8_t *buffer, uint16_t size )
 
 uint8_t SX126xReadRegister( uint16_t address )
 {
-    uint8_t data;
+    uint8_t data = 0;
     SX126xReadRegisters( address, &data, 1 );
     return data;
 }
@@ -204,6 +257,10 @@ uint8_t SX126xReadRegister( uint16_t address )
 void SX126xWriteBuffer( uint8_t offset, uint8_t *buffer, uint8_t size )
 {
     SX126xCheckDeviceReady( );
+    if( sx12xx_handle->busy_timeout == true )
+    {
+        return;
+    }
 
     GpioWrite( &SX126x.Spi.Nss, 0 );
 
@@ -221,6 +278,10 @@ void SX126xWriteBuffer( uint8_t offset, uint8_t *buffer, uint8_t size )
 void SX126xReadBuffer( uint8_t offset, uint8_t *buffer, uint8_t size )
 {
     SX126xCheckDeviceReady( );
+    if( sx12xx_handle->busy_timeout == true )
+    {
+        return;
+    }
 
     GpioWrite( &SX126x.Spi.Nss, 0 );
 
@@ -238,29 +299,55 @@ void SX126xReadBuffer( uint8_t offset, uint8_t *buffer, uint8_t size )
 
 void SX126xSetRfTxPower( int8_t power )
 {
-    if( sx12xx_handle.bindings.reduce_power!= NULL ){
-        power -= (*sx12xx_handle.bindings.reduce_power)(power);
+    if( sx12xx_handle->bindings.reduce_power!= NULL ){
+        power -= (*sx12xx_handle->bindings.reduce_power)(sx12xx_handle->bindings.context, power);
     }
     SX126xSetTxParams( power, RADIO_RAMP_40_US );
 }
 
 uint8_t SX126xGetDeviceId( void )
 {
-    return SX1262;
+    return sx12xx_handle->sx126x_profile.device_id;
 }
 
 void SX126xAntSwOn( void )
 {
-    if( sx12xx_handle.bindings.set_antenna_pins!= NULL ){
-        (*sx12xx_handle.bindings.set_antenna_pins)(AntModeTx, 0);
+    // the switch follows the operating mode, see SX126xAntSwSet
+}
+
+void SX126xAntSwSet( uint8_t mode )
+{
+    AntPinsMode_t pins;
+
+    if( sx12xx_handle->bindings.set_antenna_pins == NULL )
+    {
+        return;
+    }
+    switch( mode )
+    {
+        case MODE_TX:
+            pins = AntModeTx;
+            break;
+        case MODE_RX:
+        case MODE_RX_DC:
+        case MODE_CAD:
+            pins = AntModeRx;
+            break;
+        case MODE_SLEEP:
+            pins = AntModeSleep;
+            break;
+        default:
+            // standby and FS keep the switch where it was
+            return;
     }
+    (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, pins, 0);
 }
 
 void SX126xAntSwOff( void )
 {
 
-    if( sx12xx_handle.bindings.set_antenna_pins!= NULL ){
-        (*sx12xx_handle.bindings.set_antenna_pins)(AntModeSleep, 0);
+    if( sx12xx_handle->bindings.set_antenna_pins!= NULL ){
+        (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeSleep, 0);
     }
     RadioIsActive = false;
 }
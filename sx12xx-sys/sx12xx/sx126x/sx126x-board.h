@@ -67,7 +67,20 @@ void SX126xIoDbgInit( void );
 void SX126xReset( void );
 
 /*!
- * \brief Blocking loop to wait while the Busy pin in high
+ * \brief Time after which a Busy pin stuck high aborts the pending command
+ */
+#define SX126X_BUSY_TIMEOUT_MS                      100
+
+/*!
+ * \brief Busy pin reads before waiting in steps of 1 ms, as the radio is
+ *        usually ready within a few microseconds
+ */
+#define SX126X_BUSY_SPIN_COUNT                      1000
+
+/*!
+ * \brief Blocking loop to wait while the Busy pin in high. Gives up after
+ *        SX126X_BUSY_TIMEOUT_MS and flags the handle, after which commands
+ *        are dropped until the radio is initialized again
  */
 void SX126xWaitOnBusy( void );
 
@@ -114,6 +127,14 @@ uint8_t SX126xGetDeviceId( void );
  */
 void SX126xAntSwOn( void );
 
+/*!
+ * \brief Sets the RF switch for the given operating mode through the board's
+ *        set_antenna_pins, whether or not DIO2 drives the switch as well
+ *
+ * \param [IN] mode Operating mode the radio enters [RadioOperatingModes_t]
+ */
+void SX126xAntSwSet( uint8_t mode );
+
 /*!
  * \brief De-initializes the RF Switch I/Os pins interface
  *
@@ -136,6 +157,13 @@ bool SX126xCheckRfFrequency( uint32_t frequency );
  */
 uint32_t SX126xGetBoardTcxoWakeupTime( void );
 
+/*!
+ * \brief Gets the power regulator the board is designed for
+ *
+ * \retval mode [USE_LDO, USE_DCDC]
+ */
+uint8_t SX126xGetBoardRegulatorMode( void );
+
 /*!
  * \brief Writes new Tx debug pin state
  *
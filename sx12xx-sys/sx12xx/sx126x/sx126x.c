@@ -93,10 +93,9 @@ void SX126xInit( DioIrqHandler dioIrq )
     SX126xWakeup( );
     SX126xSetStandby( STDBY_RC );
 
-    // Initialize TCXO control
+    // Initialize TCXO control and the RF switch
     SX126xIoTcxoInit( );
 
-    SX126xSetDio2AsRfSwitchCtrl( true );
     SX126xSetOperatingMode( MODE_STDBY_RC );
 }
 
@@ -108,6 +107,7 @@ RadioOperatingModes_t SX126xGetOperatingMode( void )
 void SX126xSetOperatingMode( RadioOperatingModes_t mode )
 {
     OperatingMode = mode;
+    SX126xAntSwSet( ( uint8_t )mode );
 #if defined( USE_SX126x_RADIO_DEBUG )
     switch( mode )
     {
@@ -329,11 +329,13 @@ void SX126xSetCad( void )
 
 void SX126xSetTxContinuousWave( void )
 {
+    SX126xSetOperatingMode( MODE_TX );
     SX126xWriteCommand( SX126x_RADIO_SET_TXCONTINUOUSWAVE, 0, 0 );
 }
 
 void SX126xSetTxInfinitePreamble( void )
 {
+    SX126xSetOperatingMode( MODE_TX );
     SX126xWriteCommand( SX126x_RADIO_SET_TXCONTINUOUSPREAMBLE, 0, 0 );
 }
 
@@ -20,26 +20,29 @@
  *
  * \author    Gregory Cristian ( Semtech )
  */
+#include "./../sx12xx.h"
 #include "sx1272.h"
 #include "sx1272-board.h"
 
+extern Sx12xx_t * sx12xx_handle;
+
 /*!
  * Flag used to set the RF switch control pins in low power mode when the radio is not active.
  */
 static bool RadioIsActive = false;
+static uint8_t selected_power = 0;
+
+/*!
+ * Board TCXO state and the oscillator setup time reported by the bindings
+ */
+static bool TcxoIsOn = false;
+static uint32_t TcxoWakeupTime = SX1272_BOARD_TCXO_WAKEUP_TIME;
 
 /* !
  *  Radio hardware and global parameters defined in sx1272.c
  */
 extern SX1272_t SX1272;
 
-/*!
- * Antenna switch GPIO pins objects
- */
-LF_Gpio_t RadioSwitchCtrl1;
-LF_Gpio_t RadioSwitchCtrl2;
-LF_Gpio_t RadioPwrAmpCtrl;
-
 /*!
  * Debug GPIO pins objects
  */
@@ -93,28 +96,36 @@ void SX1272IoDbgInit( void )
 
 void SX1272IoTcxoInit( void )
 {
-    // No TCXO component available on this board design.
 }
 
 void SX1272SetBoardTcxo( uint8_t state )
 {
-    // No TCXO component available on this board design.
-#if 0
+    // if user has given board_tcxo pointer
+    // drive it and wait for the oscillator to settle when powering up
+    if( sx12xx_handle->bindings.set_board_tcxo == NULL )
+    {
+        return;
+    }
+
     if( state == true )
     {
-        TCXO_ON( );
-        DelayMs( BOARD_TCXO_WAKEUP_TIME );
+        if( TcxoIsOn == false )
+        {
+            TcxoWakeupTime = (*sx12xx_handle->bindings.set_board_tcxo)(sx12xx_handle->bindings.context, true);
+            (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, TcxoWakeupTime);
+            TcxoIsOn = true;
+        }
     }
     else
     {
-        TCXO_OFF( );
+        (*sx12xx_handle->bindings.set_board_tcxo)(sx12xx_handle->bindings.context, false);
+        TcxoIsOn = false;
     }
-#endif
 }
 
 uint32_t SX1272GetBoardTcxoWakeupTime( void )
 {
-    return SX1272_BOARD_TCXO_WAKEUP_TIME;
+    return TcxoWakeupTime;
 }
 
 void SX1272Reset( void )
@@ -122,26 +133,30 @@ void SX1272Reset( void )
     // Enables the TCXO if available on the board design
     SX1272SetBoardTcxo( true );
 
-    // Set RESET pin to 1
-    //GpioInit( &SX1272.Reset, LF_RADIO_RESET, LF_PIN_OUTPUT, LF_PIN_PUSH_PULL, LF_PIN_NO_PULL, 1 );
-
-    // Wait 1 ms
-    DelayMs( 1 );
-
-    // Configure RESET as input
-    //GpioInit( &SX1272.Reset, LF_RADIO_RESET, LF_PIN_INPUT, LF_PIN_PUSH_PULL, LF_PIN_NO_PULL, 1 );
+    (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, true);
+    (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, 1);
+    (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, false);
 
     // Wait 6 ms
-    DelayMs( 6 );
-}
-
+    (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, 6);
 
+    // reset restores the crystal input, so select the TCXO again
+    if( sx12xx_handle->bindings.set_board_tcxo != NULL )
+    {
+        SX1272Write( SX1272_REG_TCXO, ( SX1272Read( SX1272_REG_TCXO ) & RF_TCXO_TCXOINPUT_MASK ) | RF_TCXO_TCXOINPUT_ON );
+    }
+}
 
 void SX1272SetRfTxPower( int8_t power )
 {
     uint8_t paConfig = 0;
     uint8_t paDac = 0;
 
+    if( sx12xx_handle->bindings.reduce_power != NULL )
+    {
+        power -= (*sx12xx_handle->bindings.reduce_power)(sx12xx_handle->bindings.context, power);
+    }
+
     paConfig = SX1272Read( SX1272_REG_PACONFIG );
     paDac = SX1272Read( SX1272_REG_PADAC );
 
@@ -150,6 +165,12 @@ void SX1272SetRfTxPower( int8_t power )
         power = 0;
     }
 
+    // +20 dBm is the most the SX1272 can put out
+    if( power > 20 ) {
+        power = 20;
+    }
+    selected_power = power;
+
     if( power > 19 )
     {
         paConfig = ( paConfig & RF_PACONFIG_PASELECT_MASK ) | RF_PACONFIG_PASELECT_RFO;
@@ -166,19 +187,12 @@ void SX1272SetRfTxPower( int8_t power )
 
 void SX1272SetAntSwLowPower( bool status )
 {
-    if( RadioIsActive != status )
-    {
-        RadioIsActive = status;
-
-        if( status == false )
-        {
-            SX1272AntSwInit( );
-        }
-        else
-        {
-            SX1272AntSwDeInit( );
+    if(status) {
+        if( sx12xx_handle->bindings.set_antenna_pins!= NULL ){
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeSleep, 0);
         }
     }
+    RadioIsActive = status;
 }
 
 void SX1272AntSwInit( void )
@@ -191,31 +205,19 @@ void SX1272AntSwDeInit( void )
 
 void SX1272SetAntSw( uint8_t opMode )
 {
-    switch( opMode )
-    {
-    case SX1272_RFLR_OPMODE_TRANSMITTER:
-        if( ( SX1272Read( SX1272_REG_PACONFIG ) & RF_PACONFIG_PASELECT_PABOOST ) == RF_PACONFIG_PASELECT_PABOOST )
-        {
-            GpioWrite( &RadioSwitchCtrl1, 1 );
-            GpioWrite( &RadioSwitchCtrl2, 0 );
-        }
-        else
+    if( sx12xx_handle->bindings.set_antenna_pins!= NULL ){
+        switch( opMode )
         {
-            GpioWrite( &RadioSwitchCtrl1, 0 );
-            GpioWrite( &RadioSwitchCtrl2, 1 );
+        case SX1272_RFLR_OPMODE_SLEEP:
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeSleep, 0);
+            break;
+        case SX1272_RFLR_OPMODE_TRANSMITTER:
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeTx, selected_power);
+            break;
+        default:
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeRx, 0);
+            break;
         }
-        break;
-    case SX1272_RFLR_OPMODE_RECEIVER:
-    case SX1272_RFLR_OPMODE_RECEIVER_SINGLE:
-    case SX1272_RFLR_OPMODE_CAD:
-        GpioWrite( &RadioSwitchCtrl1, 1 );
-        GpioWrite( &RadioSwitchCtrl2, 1 );
-        break;
-    default:
-        GpioWrite( &RadioSwitchCtrl1, 0 );
-        GpioWrite( &RadioSwitchCtrl2, 0 );
-        GpioWrite( &RadioPwrAmpCtrl, 0 );
-        break;
     }
 }
 
@@ -283,6 +283,7 @@ bool SX1272IsChannelFree( RadioModems_t modem, uint32_t freq, int16_t rssiThresh
             status = false;
             break;
         }
+        DelayMs( 1 );
     }
     SX1272SetSleep( );
     return status;
@@ -844,8 +845,8 @@ void SX1272SetRx( uint32_t timeout )
                                                   //SX1272_RFLR_IRQFLAGS_FHSSCHANGEDCHANNEL |
                                                   SX1272_RFLR_IRQFLAGS_CADDETECTED );
 
-                // DIO0=RxDone, DIO2=FhssChangeChannel
-                SX1272Write( SX1272_REG_DIOMAPPING1, ( SX1272Read( SX1272_REG_DIOMAPPING1 ) & SX1272_RFLR_DIOMAPPING1_DIO0_MASK & SX1272_RFLR_DIOMAPPING1_DIO2_MASK  ) | SX1272_RFLR_DIOMAPPING1_DIO0_00 | SX1272_RFLR_DIOMAPPING1_DIO2_00 );
+                // DIO0=RxDone, DIO1=RxTimeout, DIO2=FhssChangeChannel
+                SX1272Write( SX1272_REG_DIOMAPPING1, ( SX1272Read( SX1272_REG_DIOMAPPING1 ) & SX1272_RFLR_DIOMAPPING1_DIO0_MASK & SX1272_RFLR_DIOMAPPING1_DIO1_MASK & SX1272_RFLR_DIOMAPPING1_DIO2_MASK  ) | SX1272_RFLR_DIOMAPPING1_DIO0_00 | SX1272_RFLR_DIOMAPPING1_DIO1_00 | SX1272_RFLR_DIOMAPPING1_DIO2_00 );
             }
             else
             {
@@ -858,8 +859,8 @@ void SX1272SetRx( uint32_t timeout )
                                                   SX1272_RFLR_IRQFLAGS_FHSSCHANGEDCHANNEL |
                                                   SX1272_RFLR_IRQFLAGS_CADDETECTED );
 
-                // DIO0=RxDone
-                SX1272Write( SX1272_REG_DIOMAPPING1, ( SX1272Read( SX1272_REG_DIOMAPPING1 ) & SX1272_RFLR_DIOMAPPING1_DIO0_MASK ) | SX1272_RFLR_DIOMAPPING1_DIO0_00 );
+                // DIO0=RxDone, DIO1=RxTimeout
+                SX1272Write( SX1272_REG_DIOMAPPING1, ( SX1272Read( SX1272_REG_DIOMAPPING1 ) & SX1272_RFLR_DIOMAPPING1_DIO0_MASK & SX1272_RFLR_DIOMAPPING1_DIO1_MASK ) | SX1272_RFLR_DIOMAPPING1_DIO0_00 | SX1272_RFLR_DIOMAPPING1_DIO1_00 );
             }
             SX1272Write( SX1272_REG_LR_FIFORXBASEADDR, 0 );
             SX1272Write( SX1272_REG_LR_FIFOADDRPTR, 0 );
@@ -1671,6 +1672,35 @@ void SX1272OnDio5Irq( void* context )
     }
 }
 
+void SX1272SetSyncWord( uint8_t *syncWord, uint8_t size )
+{
+    SX1272SetModem( MODEM_FSK );
+
+    if( size == 0 )
+    {
+        SX1272Write( SX1272_REG_SYNCCONFIG, SX1272Read( SX1272_REG_SYNCCONFIG ) & RF_SYNCCONFIG_SYNC_MASK );
+        return;
+    }
+    if( size > 8 )
+    {
+        size = 8;
+    }
+    SX1272WriteBuffer( SX1272_REG_SYNCVALUE1, syncWord, size );
+    SX1272Write( SX1272_REG_SYNCCONFIG, ( SX1272Read( SX1272_REG_SYNCCONFIG ) &
+                                      RF_SYNCCONFIG_SYNC_MASK &
+                                      RF_SYNCCONFIG_SYNCSIZE_MASK ) |
+                                      RF_SYNCCONFIG_SYNC_ON |
+                                      ( size - 1 ) );
+}
+
+void SX1272SetWhitening( bool enable )
+{
+    SX1272SetModem( MODEM_FSK );
+
+    SX1272Write( SX1272_REG_PACKETCONFIG1, ( SX1272Read( SX1272_REG_PACKETCONFIG1 ) & RF_PACKETCONFIG1_DCFREE_MASK ) |
+                                        ( ( enable == true ) ? RF_PACKETCONFIG1_DCFREE_WHITENING : RF_PACKETCONFIG1_DCFREE_OFF ) );
+}
+
 Radio_t SX1272RadioNew(){
     Radio_t radio =
     {
@@ -1702,6 +1732,9 @@ Radio_t SX1272RadioNew(){
         NULL, // void ( *RxBoosted )( uint32_t timeout ) - SX126x Only
         NULL, // void ( *SetRxDutyCycle )( uint32_t rxTime, uint32_t sleepTime ) - SX126x Only
         NULL, // void (*EnableTcxo)( void )
+        SX1272SetSyncWord,
+        SX1272SetWhitening,
+        NULL, // void ( *SetTxInfinitePreamble )( uint32_t freq, int8_t power, uint16_t time ) - SX126x Only
     };
     return radio;
 }
@@ -417,6 +417,21 @@ void SX1272SetPublicNetwork( bool enable );
  */
 uint32_t SX1272GetWakeupTime( void );
 
+/*!
+ * \brief Sets the FSK sync word
+ *
+ * \param [IN] syncWord   Sync word bytes, sent first byte first
+ * \param [IN] size       Number of sync word bytes [0: sync word off, 1..8]
+ */
+void SX1272SetSyncWord( uint8_t *syncWord, uint8_t size );
+
+/*!
+ * \brief Enables or disables the FSK data whitening
+ *
+ * \param [IN] enable     Enables the whitening
+ */
+void SX1272SetWhitening( bool enable );
+
 Radio_t SX1272RadioNew();
 
 #ifdef __cplusplus
@@ -16,7 +16,8 @@ Maintainer: Miguel Luis and Gregory Cristian
 #include "sx1276.h"
 #include "sx1276-board.h"
 
-extern Sx12xx_t sx12xx_handle;
+extern Sx12xx_t * sx12xx_handle;
+extern SX1276_t SX1276;
 
 /*!
  * Flag used to set the RF switch control pins in low power mode when the radio is not active.
@@ -45,21 +46,22 @@ void SX1276IoDeInit( void )
 
 uint8_t SX1276GetPaSelect( uint32_t channel )
 {
-    if( channel < RF_MID_BAND_THRESH )
-    {
-        return RF_PACONFIG_PASELECT_PABOOST;
-    }
-    else
+    switch( sx12xx_handle->bindings.pa_select )
     {
+    case PaSelectRfo:
         return RF_PACONFIG_PASELECT_RFO;
+    default:
+        // most modules only route PA_BOOST, which the drivers have always
+        // used; RFO takes an explicit PaSelectRfo
+        return RF_PACONFIG_PASELECT_PABOOST;
     }
 }
 
 void SX1276SetAntSwLowPower( bool status )
 {
     if(status) {
-        if( sx12xx_handle.bindings.set_antenna_pins!= NULL ){
-            (*sx12xx_handle.bindings.set_antenna_pins)(AntModeSleep, 0);
+        if( sx12xx_handle->bindings.set_antenna_pins!= NULL ){
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeSleep, 0);
         }
     }
     RadioIsActive = status;
@@ -76,17 +78,17 @@ void SX1276AntSwDeInit( void )
 void SX1276SetAntSw( uint8_t opMode )
 {
 
-    if( sx12xx_handle.bindings.set_antenna_pins!= NULL ){
+    if( sx12xx_handle->bindings.set_antenna_pins!= NULL ){
         switch( opMode )
         {
         case SX1276_RFLR_OPMODE_SLEEP:
-            (*sx12xx_handle.bindings.set_antenna_pins)(AntModeSleep, 0);
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeSleep, 0);
             break;
         case SX1276_RFLR_OPMODE_TRANSMITTER:
-            (*sx12xx_handle.bindings.set_antenna_pins)(AntModeTx, selected_power);
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeTx, selected_power);
             break;
         default:
-            (*sx12xx_handle.bindings.set_antenna_pins)(AntModeRx, 0);
+            (*sx12xx_handle->bindings.set_antenna_pins)(sx12xx_handle->bindings.context, AntModeRx, 0);
             break;
         }
     }
@@ -102,79 +104,90 @@ void SX1276Reset( )
 {
     // if user has given board_tcxo pointer
     // enable it
-    if(sx12xx_handle.bindings.set_board_tcxo!=NULL){
-        (*sx12xx_handle.bindings.reset)(true);
-        (*sx12xx_handle.bindings.delay_ms)(1);
-        (*sx12xx_handle.bindings.reset)(false);
-        uint8_t osc_setup_time = (*sx12xx_handle.bindings.set_board_tcxo)(true);
-        (*sx12xx_handle.bindings.delay_ms)(osc_setup_time);
+    if(sx12xx_handle->bindings.set_board_tcxo!=NULL){
+        (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, true);
+        (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, 1);
+        (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, false);
+        uint8_t osc_setup_time = (*sx12xx_handle->bindings.set_board_tcxo)(sx12xx_handle->bindings.context, true);
+        (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, osc_setup_time);
         SX1276EnableTcxo();
     };
 
     // reset required, even after TCXO enabling routine
-    (*sx12xx_handle.bindings.reset)(true);
-    (*sx12xx_handle.bindings.delay_ms)(1);
-    (*sx12xx_handle.bindings.reset)(false);
+    (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, true);
+    (*sx12xx_handle->bindings.delay_ms)(sx12xx_handle->bindings.context, 1);
+    (*sx12xx_handle->bindings.reset)(sx12xx_handle->bindings.context, false);
 }
 
 
 void SX1276SetRfTxPower( int8_t power ){
-    // if( sx12xx_handle.bindings.reduce_power!= NULL ){
-    //     power -= (*sx12xx_handle.bindings.reduce_power)(power);
-    // }
-    power = power;
-    // uint8_t paConfig, paDac;
-
-    // if( power < -4 )
-    // {
-    //     power = -4;
-    // }
-    // if( power > 20 )
-    // {
-    //     power = 20;
-    // }
-
-    // if( power > 15 )
-    // {
-    //     if( power > 17 )
-    //     {
-    //         paConfig = ( RF_PACONFIG_PASELECT_PABOOST | ( power - 5 ) );
-    //         paDac = RF_PADAC_20DBM_ON;
-    //     }
-    //     else
-    //     {
-    //         paConfig = ( RF_PACONFIG_PASELECT_PABOOST | ( power - 2 ) );
-    //         paDac = RF_PADAC_20DBM_OFF;
-    //     }
-    // }
-    // else
-    // {
-    //     if( power > 0 )
-    //     {
-    //         paConfig = ( RF_PACONFIG_PASELECT_RFO | ( 7 << 4 ) | ( power ) );
-    //         paDac = RF_PADAC_20DBM_OFF;
-    //     }
-    //     else
-    //     {
-    //         paConfig = ( RF_PACONFIG_PASELECT_RFO | ( 0 << 4 ) | ( power + 4 ) );
-    //         paDac = RF_PADAC_20DBM_OFF;
-    //     }
-    // }
-
-    // SX1276Write( REG_PACONFIG, paConfig );
-
-    //Pa maximum power
-    SX1276Write(SX1276_REG_PACONFIG, 0xFF);
-    
-    //150% LNA current & maximum gain
-    SX1276Write(SX1276_REG_LNA, 0x23);
+    uint8_t paConfig, paDac, ocp;
 
-    //PaDac 20dBm on PA_BOOST
-    SX1276Write(SX1276_REG_PADAC, 0x9F);
-    
-    //Set RegOcp 240ma
-    SX1276Write(SX1276_REG_OCP, 0x3B);
+    // if external PA, let the board take its share of the requested power
+    if( sx12xx_handle->bindings.reduce_power!= NULL ){
+        power -= (*sx12xx_handle->bindings.reduce_power)(sx12xx_handle->bindings.context, power);
+    }
 
+    paConfig = SX1276Read( SX1276_REG_PACONFIG );
+    paDac = SX1276Read( SX1276_REG_PADAC );
+
+    paConfig = ( paConfig & RF_PACONFIG_PASELECT_MASK ) | SX1276GetPaSelect( SX1276.Settings.Channel );
+
+    if( ( paConfig & RF_PACONFIG_PASELECT_PABOOST ) == RF_PACONFIG_PASELECT_PABOOST )
+    {
+        if( power > 17 )
+        {
+            // +20 dBm PA DAC: valid range is +5 to +20 dBm
+            if( power > 20 )
+            {
+                power = 20;
+            }
+            paDac = ( paDac & RF_PADAC_20DBM_MASK ) | RF_PADAC_20DBM_ON;
+            paConfig = ( paConfig & RF_PACONFIG_OUTPUTPOWER_MASK ) | ( uint8_t )( ( power - 5 ) & 0x0F );
+            ocp = RF_OCP_ON | RF_OCP_TRIM_240_MA;
+        }
+        else
+        {
+            // PA_BOOST: valid range is +2 to +17 dBm
+            if( power < 2 )
+            {
+                power = 2;
+            }
+            paDac = ( paDac & RF_PADAC_20DBM_MASK ) | RF_PADAC_20DBM_OFF;
+            paConfig = ( paConfig & RF_PACONFIG_OUTPUTPOWER_MASK ) | ( uint8_t )( ( power - 2 ) & 0x0F );
+            ocp = RF_OCP_ON | RF_OCP_TRIM_120_MA;
+        }
+    }
+    else
+    {
+        // RFO: valid range is -4 to +15 dBm
+        paDac = ( paDac & RF_PADAC_20DBM_MASK ) | RF_PADAC_20DBM_OFF;
+        if( power > 0 )
+        {
+            if( power > 15 )
+            {
+                power = 15;
+            }
+            paConfig = ( paConfig & RF_PACONFIG_MAX_POWER_MASK & RF_PACONFIG_OUTPUTPOWER_MASK ) | ( 7 << 4 ) | power;
+        }
+        else
+        {
+            if( power < -4 )
+            {
+                power = -4;
+            }
+            paConfig = ( paConfig & RF_PACONFIG_MAX_POWER_MASK & RF_PACONFIG_OUTPUTPOWER_MASK ) | ( 0 << 4 ) | ( power + 4 );
+        }
+        ocp = RF_OCP_ON | RF_OCP_TRIM_100_MA;
+    }
+
+    selected_power = ( power > 0 ) ? power : 0;
+
+    SX1276Write( SX1276_REG_PACONFIG, paConfig );
+    //150% LNA current & maximum gain
+    SX1276Write( SX1276_REG_LNA, 0x23 );
+    SX1276Write( SX1276_REG_PADAC, paDac );
+    SX1276Write( SX1276_REG_OCP, ocp );
 }
 
 void SX1276SetBoardTcxo(bool enable){}
@@ -294,6 +294,7 @@ bool SX1276IsChannelFree( RadioModems_t modem, uint32_t freq, int16_t rssiThresh
             status = false;
             break;
         }
+        DelayMs( 1 );
     }
     SX1276SetSleep( );
     return status;
@@ -1000,8 +1001,8 @@ void SX1276SetRx( uint32_t timeout )
                                                   //SX1276_RFLR_IRQFLAGS_FHSSCHANGEDCHANNEL |
                                                   SX1276_RFLR_IRQFLAGS_CADDETECTED );
 
-                // DIO0=RxDone, DIO2=FhssChangeChannel
-                SX1276Write( SX1276_REG_DIOMAPPING1, ( SX1276Read( SX1276_REG_DIOMAPPING1 ) & SX1276_RFLR_DIOMAPPING1_DIO0_MASK & SX1276_RFLR_DIOMAPPING1_DIO2_MASK  ) | SX1276_RFLR_DIOMAPPING1_DIO0_00 | SX1276_RFLR_DIOMAPPING1_DIO2_00 );
+                // DIO0=RxDone, DIO1=RxTimeout, DIO2=FhssChangeChannel
+                SX1276Write( SX1276_REG_DIOMAPPING1, ( SX1276Read( SX1276_REG_DIOMAPPING1 ) & SX1276_RFLR_DIOMAPPING1_DIO0_MASK & SX1276_RFLR_DIOMAPPING1_DIO1_MASK & SX1276_RFLR_DIOMAPPING1_DIO2_MASK  ) | SX1276_RFLR_DIOMAPPING1_DIO0_00 | SX1276_RFLR_DIOMAPPING1_DIO1_00 | SX1276_RFLR_DIOMAPPING1_DIO2_00 );
             }
             else
             {
@@ -1014,8 +1015,8 @@ void SX1276SetRx( uint32_t timeout )
                                                   SX1276_RFLR_IRQFLAGS_FHSSCHANGEDCHANNEL |
                                                   SX1276_RFLR_IRQFLAGS_CADDETECTED );
 
-                // DIO0=RxDone
-                SX1276Write( SX1276_REG_DIOMAPPING1, ( SX1276Read( SX1276_REG_DIOMAPPING1 ) & SX1276_RFLR_DIOMAPPING1_DIO0_MASK ) | SX1276_RFLR_DIOMAPPING1_DIO0_00 );
+                // DIO0=RxDone, DIO1=RxTimeout
+                SX1276Write( SX1276_REG_DIOMAPPING1, ( SX1276Read( SX1276_REG_DIOMAPPING1 ) & SX1276_RFLR_DIOMAPPING1_DIO0_MASK & SX1276_RFLR_DIOMAPPING1_DIO1_MASK ) | SX1276_RFLR_DIOMAPPING1_DIO0_00 | SX1276_RFLR_DIOMAPPING1_DIO1_00 );
             }
             SX1276Write( SX1276_REG_LR_FIFORXBASEADDR, 0 );
             SX1276Write( SX1276_REG_LR_FIFOADDRPTR, 0 );
@@ -1142,8 +1143,9 @@ void SX1276StartCad( void )
                                         //SX1276_RFLR_IRQFLAGS_CADDETECTED
                                         );
 
-            // DIO3=CADDone
+            // DIO3=CADDone, DIO4=CADDetected
             SX1276Write( SX1276_REG_DIOMAPPING1, ( SX1276Read( SX1276_REG_DIOMAPPING1 ) & SX1276_RFLR_DIOMAPPING1_DIO3_MASK ) | SX1276_RFLR_DIOMAPPING1_DIO3_00 );
+            SX1276Write( SX1276_REG_DIOMAPPING2, ( SX1276Read( SX1276_REG_DIOMAPPING2 ) & SX1276_RFLR_DIOMAPPING2_DIO4_MASK ) | SX1276_RFLR_DIOMAPPING2_DIO4_00 );
 
             SX1276.Settings.State = RF_CAD;
             SX1276SetOpMode( SX1276_RFLR_OPMODE_CAD );
@@ -1797,6 +1799,14 @@ void SX1276OnDio3Irq( void* context )
     case MODEM_FSK:
         break;
     case MODEM_LORA:
+        if( SX1276.Settings.State != RF_CAD )
+        {
+            // CAD already reported through the other DIO
+            break;
+        }
+        // the radio returns to standby by itself once CAD is done
+        SX1276.Settings.State = RF_IDLE;
+
         if( ( SX1276Read( SX1276_REG_LR_IRQFLAGS ) & SX1276_RFLR_IRQFLAGS_CADDETECTED ) == SX1276_RFLR_IRQFLAGS_CADDETECTED )
         {
             // Clear Irq
@@ -1834,6 +1844,12 @@ void SX1276OnDio4Irq( void* context )
         }
         break;
     case MODEM_LORA:
+        // DIO4=CADDetected, boards may only wire this line up
+        if( ( SX1276.Settings.State == RF_CAD ) &&
+            ( ( SX1276Read( SX1276_REG_LR_IRQFLAGS ) & SX1276_RFLR_IRQFLAGS_CADDONE ) == SX1276_RFLR_IRQFLAGS_CADDONE ) )
+        {
+            SX1276OnDio3Irq( context );
+        }
         break;
     default:
         break;
@@ -1859,6 +1875,35 @@ void SX1276EnableTcxo( void ){
     SX1276Write( SX1276_REG_OPMODE, ( SX1276Read( SX1276_REG_OPMODE ) & RF_OPMODE_MASK ) | RF_OPMODE_SLEEP );
 }
 
+void SX1276SetSyncWord( uint8_t *syncWord, uint8_t size )
+{
+    SX1276SetModem( MODEM_FSK );
+
+    if( size == 0 )
+    {
+        SX1276Write( SX1276_REG_SYNCCONFIG, SX1276Read( SX1276_REG_SYNCCONFIG ) & RF_SYNCCONFIG_SYNC_MASK );
+        return;
+    }
+    if( size > 8 )
+    {
+        size = 8;
+    }
+    SX1276WriteBuffer( SX1276_REG_SYNCVALUE1, syncWord, size );
+    SX1276Write( SX1276_REG_SYNCCONFIG, ( SX1276Read( SX1276_REG_SYNCCONFIG ) &
+                                      RF_SYNCCONFIG_SYNC_MASK &
+                                      RF_SYNCCONFIG_SYNCSIZE_MASK ) |
+                                      RF_SYNCCONFIG_SYNC_ON |
+                                      ( size - 1 ) );
+}
+
+void SX1276SetWhitening( bool enable )
+{
+    SX1276SetModem( MODEM_FSK );
+
+    SX1276Write( SX1276_REG_PACKETCONFIG1, ( SX1276Read( SX1276_REG_PACKETCONFIG1 ) & RF_PACKETCONFIG1_DCFREE_MASK ) |
+                                        ( ( enable == true ) ? RF_PACKETCONFIG1_DCFREE_WHITENING : RF_PACKETCONFIG1_DCFREE_OFF ) );
+}
+
 Radio_t SX1276RadioNew(){
         Radio_t radio = {
         SX1276Init,
@@ -1889,6 +1934,9 @@ Radio_t SX1276RadioNew(){
         NULL, // void ( *RxBoosted )( uint32_t timeout ) - SX126x Only
         NULL, // void ( *SetRxDutyCycle )( uint32_t rxTime, uint32_t sleepTime ) - SX126x Only
         SX1276EnableTcxo,
+        SX1276SetSyncWord,
+        SX1276SetWhitening,
+        NULL, // void ( *SetTxInfinitePreamble )( uint32_t freq, int8_t power, uint16_t time ) - SX126x Only
     };
     return radio;
 }
\ No newline at end of file
@@ -427,6 +427,21 @@ void SX1276SetPublicNetwork( bool enable );
  */
 uint32_t SX1276GetWakeupTime( void );
 
+/*!
+ * \brief Sets the FSK sync word
+ *
+ * \param [IN] syncWord   Sync word bytes, sent first byte first
+ * \param [IN] size       Number of sync word bytes [0: sync word off, 1..8]
+ */
+void SX1276SetSyncWord( uint8_t *syncWord, uint8_t size );
+
+/*!
+ * \brief Enables or disables the FSK data whitening
+ *
+ * \param [IN] enable     Enables the whitening
+ */
+void SX1276SetWhitening( bool enable );
+
 Radio_t SX1276RadioNew();
 
 void SX1276EnableTcxo( void );
@@ -2,7 +2,8 @@
 #include <string.h>
 #include "sx12xx.h"
 
-Sx12xx_t sx12xx_handle;
+// the handle the drivers and board files currently act on, see sx12xx_select
+Sx12xx_t * sx12xx_handle;
 
 void OnTxDone(void);
 
@@ -14,69 +15,132 @@ void OnRxTimeout(void);
 
 void OnRxError(void);
 
+void OnCadDone(bool channelActivityDetected);
+
+void OnFhssChangeChannel(uint8_t currentChannel);
+
+// the drivers keep a pointer to their events, so these are shared by all
+// handles; the callbacks act on the selected handle
+static RadioEvents_t sx12xx_radio_events = {
+    .TxDone            = OnTxDone,
+    .RxDone            = OnRxDone,
+    .TxTimeout         = OnTxTimeout,
+    .RxTimeout         = OnRxTimeout,
+    .RxError           = OnRxError,
+    .CadDone           = OnCadDone,
+    .FhssChangeChannel = OnFhssChangeChannel,
+};
+
+Sx12xx_t
+sx12xx_new_handle(void)
+{
+    Sx12xx_t handle;
+    memset(&handle, 0, sizeof(handle));
+    handle.sx126x_profile.tcxo                = true;
+    handle.sx126x_profile.tcxo_voltage        = 0x01; // 1.7 V
+    handle.sx126x_profile.tcxo_wakeup_time_ms = 6;
+    handle.sx126x_profile.dio2_rf_switch      = true;
+    handle.sx126x_profile.dcdc                = true;
+    handle.sx126x_profile.device_id           = 2; // SX1262
+    return handle;
+}
+
+void
+sx12xx_select(Sx12xx_t * handle)
+{
+    sx12xx_handle = handle;
+}
+
 void 
-sx12xx_init(Radio_t * radio, BoardBindings_t bindings)
+sx12xx_init(Sx12xx_t * handle, Radio_t * radio, BoardBindings_t bindings)
+{
+    sx12xx_select(handle);
+    sx12xx_handle->bindings = bindings;
+    sx12xx_recover(handle, radio);
+}
+
+bool
+sx12xx_recover(Sx12xx_t * handle, Radio_t * radio)
 {
-    sx12xx_handle.bindings = bindings;
-    // configure sx12xx radio events with local functions
-    sx12xx_handle.radio_events.TxDone    = OnTxDone;
-    sx12xx_handle.radio_events.RxDone    = OnRxDone;
-    sx12xx_handle.radio_events.TxTimeout = OnTxTimeout;
-    sx12xx_handle.radio_events.RxTimeout = OnRxTimeout;
-    sx12xx_handle.radio_events.RxError   = OnRxError;
+    sx12xx_select(handle);
+    sx12xx_handle->busy_timeout = false;
+    // timers are registered again by the driver's TimerInit calls
+    sx12xx_handle->num_timers = 0;
 
-    // this function calls TimerInits and radio->IoIrqInit, which are
-    // implemented here
-    radio->Init(&sx12xx_handle.radio_events);
+    // this function resets the radio and calls TimerInits and
+    // radio->IoIrqInit, which are implemented here
+    radio->Init(&sx12xx_radio_events);
 
     // sleep the radio and wait for a send or receive call
     radio->Sleep();
+    return !sx12xx_handle->busy_timeout;
 }
 
 
 
+// not every driver has a handler behind every DIO line
+static void
+sx12xx_dispatch_irq(uint8_t dio)
+{
+    if (sx12xx_handle->dio_irq_handles[dio] == NULL)
+    {
+        sx12xx_handle->state = Sx12xxState_MissingIrqHandler;
+        return;
+    }
+    (*(sx12xx_handle->dio_irq_handles[dio]))(NULL);
+}
+
 Sx12xxState_t
-sx12xx_handle_event(Sx12xxEvent_t event)
+sx12xx_handle_event(Sx12xx_t * handle, Sx12xxEvent_t event)
 {
+    sx12xx_select(handle);
     // initialize state here but the callbacks from
     // the Semtech library (define below) may alter it
-    sx12xx_handle.state = Sx12xxState_Busy;
+    sx12xx_handle->state = Sx12xxState_Busy;
 
     switch (event)
     {
     case Sx12xxEvent_DIO0:
-        (*(sx12xx_handle.dio_irq_handles[0]))();
+        sx12xx_dispatch_irq(0);
         break;
     case Sx12xxEvent_DIO1:
-        // SX126x library only has the one handle, so fire it even fore DIO1
-        (*(sx12xx_handle.dio_irq_handles[0]))();
+        // SX126x library only has the one handle, so fire it even fore DIO1;
+        // SX127x drivers have a dedicated one
+        if (sx12xx_handle->dio_irq_handles[1] == NULL)
+        {
+            sx12xx_dispatch_irq(0);
+        }
+        else
+        {
+            sx12xx_dispatch_irq(1);
+        }
         break;
     case Sx12xxEvent_DIO2:
-        (*(sx12xx_handle.dio_irq_handles[2]))();
+        sx12xx_dispatch_irq(2);
         break;
     case Sx12xxEvent_DIO3:
-        (*(sx12xx_handle.dio_irq_handles[3]))();
+        sx12xx_dispatch_irq(3);
         break;
     case Sx12xxEvent_DIO4:
-        (*(sx12xx_handle.dio_irq_handles[4]))();
+        sx12xx_dispatch_irq(4);
         break;
     case Sx12xxEvent_DIO5:
-        (*(sx12xx_handle.dio_irq_handles[5]))();
+        sx12xx_dispatch_irq(5);
         break;
     case Sx12xxEvent_Timer1:
-        // TODO: needs to dispatch the callback stashed from TimerInit
+        TimerIrqDispatch(0);
         break;
     case Sx12xxEvent_Timer2:
-        // TODO: needs to dispatch the callback stashed from TimerInit
+        TimerIrqDispatch(1);
         break;
     case Sx12xxEvent_Timer3:
-        // TODO: needs to dispatch the callback stashed from TimerInit
+        TimerIrqDispatch(2);
         break;
     default:
         break;
     }
 
-    return sx12xx_handle.state;
+    return sx12xx_handle->state;
 }
 
 // each sx12xx board invokes this during initialization
@@ -85,36 +149,63 @@ IoIrqInit(IrqHandler * irq_handlers[NUM_IRQ_HANDLES])
 {
     for (uint32_t i = 0; i < NUM_IRQ_HANDLES; i++)
     {
-        sx12xx_handle.dio_irq_handles[i] = irq_handlers[i];
+        sx12xx_handle->dio_irq_handles[i] = irq_handlers[i];
     }
 }
 
 void
 OnTxDone(void)
 {
-    sx12xx_handle.state = Sx12xxState_TxDone;
+    sx12xx_handle->state = Sx12xxState_TxDone;
 }
 
-uint8_t * sx12xx_get_raw_buffer() {
-    return sx12xx_handle.raw_buffer;
+uint8_t * sx12xx_get_raw_buffer(Sx12xx_t * handle) {
+    return handle->raw_buffer;
 }
 
 void
 OnRxDone(uint8_t * payload, uint16_t size, int16_t rssi, int8_t snr)
 {
-    sx12xx_handle.raw_buffer = payload;
-    sx12xx_handle.state = Sx12xxState_RxDone;
-    sx12xx_handle.rx_metadata.rx_len = size;
-    sx12xx_handle.rx_metadata.rssi = rssi;
-    sx12xx_handle.rx_metadata.snr  = snr;
+    sx12xx_handle->raw_buffer = payload;
+    sx12xx_handle->rx_metadata.rx_len = size;
+    sx12xx_handle->rx_metadata.rssi = rssi;
+    sx12xx_handle->rx_metadata.snr  = snr;
+
+    if (sx12xx_handle->rx_buffer != NULL)
+    {
+        if (size > sx12xx_handle->rx_buffer_len)
+        {
+            sx12xx_handle->state = Sx12xxState_RxOverflow;
+            return;
+        }
+        memcpy(sx12xx_handle->rx_buffer, payload, size);
+    }
+    sx12xx_handle->state = Sx12xxState_RxDone;
+}
+
+void
+sx12xx_set_rx_buffer(Sx12xx_t * handle, uint8_t * buf, uint8_t len)
+{
+    handle->rx_buffer     = buf;
+    handle->rx_buffer_len = len;
+}
+
+uint8_t *
+sx12xx_take_rx_buffer(Sx12xx_t * handle)
+{
+    uint8_t * buf = handle->rx_buffer;
+
+    handle->rx_buffer     = NULL;
+    handle->rx_buffer_len = 0;
+    return buf;
 }
 
 Sx12xxRxMetadata_t 
-sx12xx_get_rx_metadata() {
+sx12xx_get_rx_metadata(Sx12xx_t * handle) {
     Sx12xxRxMetadata_t metadata  = {
-        .rx_len = sx12xx_handle.rx_metadata.rx_len,
-        .rssi = sx12xx_handle.rx_metadata.rssi,
-        .snr = sx12xx_handle.rx_metadata.snr,
+        .rx_len = handle->rx_metadata.rx_len,
+        .rssi = handle->rx_metadata.rssi,
+        .snr = handle->rx_metadata.snr,
     };
     return metadata;
 }
@@ -122,17 +213,44 @@ sx12xx_get_rx_metadata() {
 void
 OnTxTimeout(void)
 {
-    sx12xx_handle.state = Sx12xxState_TxTimeout;
+    sx12xx_handle->state = Sx12xxState_TxTimeout;
 }
 
 void
 OnRxTimeout(void)
 {
-    sx12xx_handle.state = Sx12xxState_RxTimeout;
+    sx12xx_handle->state = Sx12xxState_RxTimeout;
 }
 
 void
 OnRxError(void)
 {
-    sx12xx_handle.state = Sx12xxState_RxError;
-}
\ No newline at end of file
+    sx12xx_handle->state = Sx12xxState_RxError;
+}
+
+void
+OnCadDone(bool channelActivityDetected)
+{
+    sx12xx_handle->cad_activity_detected = channelActivityDetected;
+    sx12xx_handle->state = Sx12xxState_CadDone;
+}
+
+bool
+sx12xx_get_cad_activity_detected(Sx12xx_t * handle)
+{
+    return handle->cad_activity_detected;
+}
+
+void
+OnFhssChangeChannel(uint8_t currentChannel)
+{
+    // the next frequency is picked by the client from its hop table
+    sx12xx_handle->fhss_channel = currentChannel;
+    sx12xx_handle->state = Sx12xxState_FhssChangeChannel;
+}
+
+uint8_t
+sx12xx_get_fhss_channel(Sx12xx_t * handle)
+{
+    return handle->fhss_channel;
+}
@@ -15,14 +15,14 @@ extern "C"
     typedef enum Sx12xxEvent_t
     {
         Sx12xxEvent_DIO0,   // TxDone or Rx
-        Sx12xxEvent_DIO1,   // unimplemented
-        Sx12xxEvent_DIO2,   // unimplemented
-        Sx12xxEvent_DIO3,   // unimplemented
-        Sx12xxEvent_DIO4,   // unimplemented
-        Sx12xxEvent_DIO5,   // unimplemented
-        Sx12xxEvent_Timer1, // unimplemented
-        Sx12xxEvent_Timer2, // unimplemented
-        Sx12xxEvent_Timer3 = 0xFFFFFFFF // force 32-bit value,
+        Sx12xxEvent_DIO1,   // SX127x: RxTimeout, or FSK FIFO level; SX126x: every irq
+        Sx12xxEvent_DIO2,   // FhssChangeChannel
+        Sx12xxEvent_DIO3,   // SX127x LoRa: CadDone
+        Sx12xxEvent_DIO4,   // SX127x FSK: preamble detected; LoRa: CadDone
+        Sx12xxEvent_DIO5,   // no driver handles it; reports MissingIrqHandler
+        Sx12xxEvent_Timer1, // timer_id 0 expired
+        Sx12xxEvent_Timer2, // timer_id 1 expired
+        Sx12xxEvent_Timer3 = 0xFFFFFFFF // timer_id 2 expired; force 32-bit value,
     } Sx12xxEvent_t;
 
     typedef enum Sx12xxState_t
@@ -32,10 +32,25 @@ extern "C"
         Sx12xxState_RxDone,
         Sx12xxState_TxTimeout,
         Sx12xxState_RxTimeout,
+        Sx12xxState_CadDone,
+        Sx12xxState_FhssChangeChannel,
+        Sx12xxState_MissingIrqHandler, // the driver does not handle this DIO
+        Sx12xxState_RxOverflow, // the frame does not fit the client's rx buffer
         Sx12xxState_RxError = 0xFFFFFFFF // force 32-bit value,
     } Sx12xxState_t;
 
 
+    // how an SX126x is wired on the board; the SX127x drivers ignore it
+    typedef struct Sx126xProfile_t
+    {
+        bool     tcxo;                // TCXO supplied from DIO3, else a crystal
+        uint8_t  tcxo_voltage;        // RadioTcxoCtrlVoltage_t, 0x00 (1.6 V) to 0x07 (3.3 V)
+        uint32_t tcxo_wakeup_time_ms;
+        bool     dio2_rf_switch;      // DIO2 drives the RF switch
+        bool     dcdc;                // DC-DC regulator, else LDO
+        uint8_t  device_id;           // PA of the SX1261 (1) or the SX1262 (2)
+    } Sx126xProfile_t;
+
     typedef struct Sx12xxRxMetadata_t {
         uint16_t rx_len;
         int16_t rssi;
@@ -45,14 +60,25 @@ extern "C"
     typedef struct Sx12xx
     {
         void (*dio_irq_handles[NUM_IRQ_HANDLES])();
+        TimerEvent_t * timers[NUM_TIMERS];
+        uint8_t num_timers;
         BoardBindings_t bindings;
         Radio_t radio;
-        RadioEvents_t radio_events;
         Sx12xxState_t state;
         Sx12xxRxMetadata_t rx_metadata;
-        int8_t * rx_buffer;
-        int8_t rx_buffer_len;
-        int8_t * raw_buffer;
+        bool cad_activity_detected;
+        uint8_t fhss_channel;
+        uint8_t * rx_buffer;
+        uint8_t rx_buffer_len;
+        uint8_t * raw_buffer;
+        // time spent in DelayMs, the clock of last resort
+        uint32_t delay_time_ms;
+        // set before sx12xx_init; sx12xx_new_handle fills in the SX1262MBXCAS
+        // shield
+        Sx126xProfile_t sx126x_profile;
+        // the SX126x BUSY line stayed high past SX126X_BUSY_TIMEOUT_MS; the
+        // drivers drop commands until sx12xx_recover
+        bool busy_timeout;
     } Sx12xx_t;
 
     Sx12xx_t sx12xx_new_handle(void);
@@ -60,28 +86,63 @@ extern "C"
      * \brief  Run time initialization of library
      *
      */
-    void sx12xx_init(Radio_t *, BoardBindings_t);
+    void sx12xx_init(Sx12xx_t *, Radio_t *, BoardBindings_t);
+
+    /*!
+     * \brief  Reset and initialize the radio again with the bindings given to
+     *         sx12xx_init, clearing busy_timeout. Returns false if the radio
+     *         is still unresponsive. Settings made through Radio_t are lost.
+     *
+     */
+    bool sx12xx_recover(Sx12xx_t *, Radio_t *);
+
+    /*!
+     * \brief  Make the drivers act on the given handle until another one is
+     *         selected. Must be called before invoking any Radio_t function
+     *         when more than one handle is in use. Each handle must drive a
+     *         different chip family, as the chip drivers keep their own state.
+     *
+     */
+    void sx12xx_select(Sx12xx_t *);
 
-    uint8_t * sx12xx_get_raw_buffer();
+    uint8_t * sx12xx_get_raw_buffer(Sx12xx_t *);
 
 
 /*!
      * \brief To be used by client in a low-priorty loop, feeding events into the library
      *
      */
-    Sx12xxState_t sx12xx_handle_event(Sx12xxEvent_t);
+    Sx12xxState_t sx12xx_handle_event(Sx12xx_t *, Sx12xxEvent_t);
 
     void
     sx12xx_send(Radio_t * radio, const uint8_t * data, size_t len);
 
-    void 
-    sx12xx_set_rx_buffer(uint8_t * buf, uint8_t len);
+    /*!
+     * \brief  Have received frames copied straight into buf, which must hold
+     *         up to len bytes, rather than left in the driver's buffer. Frames
+     *         that do not fit are dropped and reported as
+     *         Sx12xxState_RxOverflow.
+     *
+     */
+    void
+    sx12xx_set_rx_buffer(Sx12xx_t *, uint8_t * buf, uint8_t len);
 
-    void 
-    sx12xx_take_rx_buffer();
+    /*!
+     * \brief  Detach and return the buffer given to sx12xx_set_rx_buffer, if
+     *         any; received frames are then only left in the driver's buffer
+     *
+     */
+    uint8_t *
+    sx12xx_take_rx_buffer(Sx12xx_t *);
 
     Sx12xxRxMetadata_t 
-    sx12xx_get_rx_metadata();
+    sx12xx_get_rx_metadata(Sx12xx_t *);
+
+    bool
+    sx12xx_get_cad_activity_detected(Sx12xx_t *);
+
+    uint8_t
+    sx12xx_get_fhss_channel(Sx12xx_t *);
 
 #ifdef __cplusplus
 }
@@ -0,0 +1,116 @@
+// Times on air checked against the Semtech LoRa calculator and the FSK
+// packet format of AN1200.13
+use sx12xx::{FskAirtime, LoRaAirtime, LoRaBandwidth, LoRaCodingRate, LoRaSpreadingFactor};
+
+// as LoRaWAN uplinks: 8 symbol preamble, explicit header, CRC on
+fn lora(spreading_factor: LoRaSpreadingFactor, bandwidth: LoRaBandwidth) -> LoRaAirtime {
+    LoRaAirtime {
+        spreading_factor,
+        bandwidth,
+        coding_rate: LoRaCodingRate::_4_5,
+        preamble_len: 8,
+        implicit_header: false,
+        crc_on: true,
+        low_data_rate_optimize: false,
+    }
+}
+
+#[test]
+fn lora_sf7_125khz() {
+    let airtime = lora(LoRaSpreadingFactor::_7, LoRaBandwidth::_125KHZ);
+    assert_eq!(airtime.time_on_air_us(13), 46_336);
+    assert_eq!(airtime.time_on_air_us(51), 102_656);
+}
+
+#[test]
+fn lora_sf8_500khz() {
+    let airtime = lora(LoRaSpreadingFactor::_8, LoRaBandwidth::_500KHZ);
+    assert_eq!(airtime.time_on_air_us(13), 20_608);
+}
+
+#[test]
+fn lora_sf12_125khz_low_data_rate_optimize() {
+    let airtime = LoRaAirtime {
+        low_data_rate_optimize: true,
+        ..lora(LoRaSpreadingFactor::_12, LoRaBandwidth::_125KHZ)
+    };
+    assert_eq!(airtime.time_on_air_us(13), 1_155_072);
+    assert_eq!(airtime.time_on_air_us(51), 2_465_792);
+    // without it, blocks hold more bits
+    let airtime = LoRaAirtime {
+        low_data_rate_optimize: false,
+        ..airtime
+    };
+    assert_eq!(airtime.time_on_air_us(51), 2_138_112);
+}
+
+#[test]
+fn lora_implicit_header() {
+    let airtime = LoRaAirtime {
+        implicit_header: true,
+        ..lora(LoRaSpreadingFactor::_7, LoRaBandwidth::_125KHZ)
+    };
+    assert_eq!(airtime.time_on_air_us(13), 41_216);
+    // SF6 only works with an implicit header
+    let airtime = LoRaAirtime {
+        implicit_header: true,
+        crc_on: false,
+        ..lora(LoRaSpreadingFactor::_6, LoRaBandwidth::_125KHZ)
+    };
+    assert_eq!(airtime.time_on_air_us(10), 18_048);
+}
+
+#[test]
+fn lora_crc_off() {
+    let airtime = LoRaAirtime {
+        crc_on: false,
+        ..lora(LoRaSpreadingFactor::_9, LoRaBandwidth::_125KHZ)
+    };
+    assert_eq!(airtime.time_on_air_us(22), 185_344);
+    let airtime = LoRaAirtime {
+        crc_on: true,
+        ..airtime
+    };
+    assert_eq!(airtime.time_on_air_us(22), 205_824);
+}
+
+#[test]
+fn lora_empty_payload() {
+    // preamble and the 8 symbols every packet has
+    let airtime = LoRaAirtime {
+        implicit_header: true,
+        crc_on: false,
+        low_data_rate_optimize: true,
+        ..lora(LoRaSpreadingFactor::_12, LoRaBandwidth::_125KHZ)
+    };
+    assert_eq!(airtime.time_on_air_us(0), 663_552);
+}
+
+#[test]
+fn fsk() {
+    // LoRaWAN 50 kbps: 5 byte preamble, 3 byte sync word, length byte, CRC
+    let airtime = FskAirtime {
+        datarate: 50_000,
+        preamble_len: 5,
+        sync_word_len: 3,
+        fix_len: false,
+        crc_on: true,
+    };
+    assert_eq!(airtime.time_on_air_us(13), 3_840);
+    assert_eq!(airtime.time_on_air_us(0), 1_760);
+    let airtime = FskAirtime {
+        datarate: 4_800,
+        preamble_len: 3,
+        sync_word_len: 2,
+        fix_len: true,
+        crc_on: false,
+    };
+    assert_eq!(airtime.time_on_air_us(10), 25_000);
+    // rounded up to the next microsecond
+    let airtime = FskAirtime {
+        datarate: 300_000,
+        ..airtime
+    };
+    assert_eq!(airtime.time_on_air_us(10), 400);
+    assert_eq!(airtime.time_on_air_us(11), 427);
+}
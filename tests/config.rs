@@ -0,0 +1,190 @@
+// LoRaConfig and FskConfig defaults, and the configurations apply_tx and
+// apply_rx turn away for each chip family
+#![cfg(feature = "mock")]
+use sx12xx::emulator::Sx126xEmulator;
+use sx12xx::mock::Mock;
+use sx12xx::{
+    Error, FskConfig, LoRaBandwidth, LoRaCodingRate, LoRaConfig, LoRaSpreadingFactor, Radio,
+    RadioConfig, Sx12xx,
+};
+
+// the mock stands in for an SX1276; Mock::take() serializes its users
+fn sx1276(mock: &Mock) -> Sx12xx {
+    Sx12xx::new(Radio::mock(), mock.bindings()).unwrap()
+}
+
+fn lora(spreading_factor: LoRaSpreadingFactor) -> LoRaConfig {
+    LoRaConfig::new(
+        spreading_factor,
+        LoRaBandwidth::_125KHZ,
+        LoRaCodingRate::_4_5,
+    )
+}
+
+fn assert_invalid(result: Result<(), Error>) {
+    assert!(
+        matches!(result, Err(Error::InvalidConfig)),
+        "expected InvalidConfig, got {:?}",
+        result
+    );
+}
+
+#[test]
+fn lora_defaults() {
+    let config = lora(LoRaSpreadingFactor::_7);
+    assert_eq!(config.preamble_len, 8);
+    assert_eq!(config.implicit_header, None);
+    assert!(config.crc_on);
+    assert!(!config.iq_inverted);
+    assert_eq!(config.low_data_rate_optimize, None);
+    assert_eq!(config.sync_word, None);
+    assert_eq!(config.hop_period, None);
+    assert_eq!(config.power, 14);
+    assert_eq!(config.tx_timeout_ms, 3000);
+    assert_eq!(config.symbol_timeout, 5);
+    assert!(config.continuous_rx);
+    assert!(!config.default_low_data_rate_optimize());
+    assert!(lora(LoRaSpreadingFactor::_11).default_low_data_rate_optimize());
+
+    let config = config
+        .implicit_header(12)
+        .crc_on(false)
+        .power(20)
+        .continuous_rx(false);
+    assert_eq!(config.implicit_header, Some(12));
+    assert!(!config.crc_on);
+    assert_eq!(config.power, 20);
+    assert!(!config.continuous_rx);
+    assert_eq!(config.explicit_header().implicit_header, None);
+}
+
+#[test]
+fn fsk_defaults() {
+    let config = FskConfig::new(50_000, 25_000, 100_000);
+    assert_eq!(config.bandwidth_afc, 100_000);
+    assert_eq!(config.preamble_len, 5);
+    assert_eq!(config.fix_len, None);
+    assert!(config.crc_on);
+    assert_eq!(&config.sync_word[..], &[0xC1, 0x94, 0xC1]);
+    assert!(config.whitening);
+    assert_eq!(config.power, 14);
+    assert_eq!(config.tx_timeout_ms, 3000);
+    assert!(config.continuous_rx);
+
+    assert_eq!(config.clone().fix_len(32).fix_len, Some(32));
+    assert!(config.clone().sync_word(&[0x2D; 8]).is_ok());
+    assert!(matches!(
+        config.sync_word(&[0x2D; 9]),
+        Err(Error::InvalidSyncWord)
+    ));
+}
+
+#[test]
+fn sx1276_lora_limits() {
+    let mock = Mock::take();
+    let mut sx12xx = sx1276(&mock);
+
+    // SF6 needs an implicit header
+    assert_invalid(sx12xx.apply_tx(lora(LoRaSpreadingFactor::_6)));
+    assert_invalid(sx12xx.apply_rx(lora(LoRaSpreadingFactor::_6)));
+    sx12xx
+        .apply_tx(lora(LoRaSpreadingFactor::_6).implicit_header(16))
+        .unwrap();
+
+    let reserved = LoRaConfig::new(
+        LoRaSpreadingFactor::_7,
+        LoRaBandwidth::_RESERVED,
+        LoRaCodingRate::_4_5,
+    );
+    assert_invalid(sx12xx.apply_tx(reserved));
+    assert_invalid(sx12xx.apply_tx(lora(LoRaSpreadingFactor::_7).implicit_header(0)));
+    // 10 bit symbol timeout
+    sx12xx
+        .apply_rx(lora(LoRaSpreadingFactor::_7).symbol_timeout(0x3FF))
+        .unwrap();
+    assert_invalid(sx12xx.apply_rx(lora(LoRaSpreadingFactor::_7).symbol_timeout(0x400)));
+    for &coding_rate in &[
+        LoRaCodingRate::_4_5,
+        LoRaCodingRate::_4_6,
+        LoRaCodingRate::_4_7,
+        LoRaCodingRate::_4_8,
+    ] {
+        let config = LoRaConfig {
+            coding_rate,
+            ..lora(LoRaSpreadingFactor::_7)
+        };
+        sx12xx.apply_tx(config).unwrap();
+    }
+
+    // a rejected configuration leaves the one in use alone
+    let in_use = lora(LoRaSpreadingFactor::_9).power(10);
+    sx12xx.apply_tx(in_use.clone()).unwrap();
+    assert_invalid(sx12xx.apply_tx(lora(LoRaSpreadingFactor::_6)));
+    assert_eq!(sx12xx.get_tx_config(), Some(&RadioConfig::LoRa(in_use)));
+}
+
+#[test]
+fn sx1276_fsk_limits() {
+    let mock = Mock::take();
+    let mut sx12xx = sx1276(&mock);
+
+    sx12xx
+        .apply_tx(FskConfig::new(50_000, 25_000, 100_000))
+        .unwrap();
+    sx12xx
+        .apply_tx(FskConfig::new(1_200, 200_000, 2_600))
+        .unwrap();
+    sx12xx
+        .apply_tx(FskConfig::new(300_000, 0, 250_000))
+        .unwrap();
+    // frequency deviation
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(50_000, 200_001, 100_000)));
+    // bitrate
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(1_199, 25_000, 100_000)));
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(300_001, 25_000, 100_000)));
+    // receiver and AFC bandwidth
+    assert_invalid(sx12xx.apply_rx(FskConfig::new(50_000, 25_000, 2_599)));
+    assert_invalid(sx12xx.apply_rx(FskConfig::new(50_000, 25_000, 300_000)));
+    assert_invalid(sx12xx.apply_rx(FskConfig::new(50_000, 25_000, 100_000).bandwidth_afc(0)));
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(50_000, 25_000, 100_000).fix_len(0)));
+
+    // SetTxConfig leaves the bandwidths alone, so what configure_fsk_tx
+    // stores goes back in
+    sx12xx.configure_fsk_tx(14, 25_000, 50_000);
+    let config = sx12xx.get_tx_config().unwrap().clone();
+    sx12xx.apply_tx(config.clone()).unwrap();
+    assert_invalid(sx12xx.apply_rx(config));
+}
+
+#[test]
+fn sx126x_limits() {
+    let chip = Sx126xEmulator::new();
+    let mut sx12xx = Sx12xx::new(Radio::sx1262(), chip.bindings()).unwrap();
+
+    // SF6 has a header on SX126x
+    sx12xx.apply_tx(lora(LoRaSpreadingFactor::_6)).unwrap();
+    // low data rate optimization follows the drivers' rule
+    sx12xx
+        .apply_tx(lora(LoRaSpreadingFactor::_12).low_data_rate_optimize(true))
+        .unwrap();
+    assert_invalid(sx12xx.apply_tx(lora(LoRaSpreadingFactor::_7).low_data_rate_optimize(true)));
+    // 8 bit symbol timeout
+    sx12xx
+        .apply_rx(lora(LoRaSpreadingFactor::_7).symbol_timeout(255))
+        .unwrap();
+    assert_invalid(sx12xx.apply_rx(lora(LoRaSpreadingFactor::_7).symbol_timeout(256)));
+
+    sx12xx.apply_tx(FskConfig::new(600, 25_000, 4_800)).unwrap();
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(599, 25_000, 100_000)));
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(300_001, 25_000, 400_000)));
+    assert_invalid(sx12xx.apply_rx(FskConfig::new(50_000, 25_000, 4_799)));
+    assert_invalid(sx12xx.apply_rx(FskConfig::new(50_000, 25_000, 500_000)));
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(50_000, 200_001, 100_000)));
+
+    // a TX bandwidth of 0 is left to the drivers, as configure_fsk_tx does
+    sx12xx.configure_fsk_tx(14, 25_000, 50_000);
+    let config = sx12xx.get_tx_config().unwrap().clone();
+    sx12xx.apply_tx(config.clone()).unwrap();
+    assert_invalid(sx12xx.apply_rx(config));
+    assert_invalid(sx12xx.apply_tx(FskConfig::new(50_000, 25_000, 4_799)));
+}
@@ -0,0 +1,103 @@
+// Chip detection against the emulators and against boards with nothing on
+// the bus
+#![cfg(feature = "mock")]
+use core::ffi::c_void;
+use std::sync::{Mutex, MutexGuard};
+use sx12xx::emulator::{Sx126xEmulator, Sx1276Emulator};
+use sx12xx::{BoardBindings, Chip, Error, PaSelect, Sx126xProfile, Sx12xx};
+
+// only one Sx12xx per chip family may be alive
+static DRIVERS: Mutex<()> = Mutex::new(());
+
+fn drivers() -> MutexGuard<'static, ()> {
+    DRIVERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+extern "C" fn miso_low(_context: *mut c_void, _out_data: u8) -> u8 {
+    0x00
+}
+
+extern "C" fn miso_high(_context: *mut c_void, _out_data: u8) -> u8 {
+    0xFF
+}
+
+extern "C" fn ignore_pin(_context: *mut c_void, _level: bool) {}
+
+extern "C" fn delay_ms(_context: *mut c_void, _ms: u32) {}
+
+extern "C" fn not_busy(_context: *mut c_void) -> bool {
+    false
+}
+
+// a board with no chip fitted; MISO reads back as miso
+fn empty_socket(
+    miso: unsafe extern "C" fn(*mut c_void, u8) -> u8,
+    busy_pin_status: Option<unsafe extern "C" fn(*mut c_void) -> bool>,
+) -> BoardBindings {
+    BoardBindings {
+        context: core::ptr::null_mut(),
+        spi_in_out: Some(miso),
+        spi_nss: Some(ignore_pin),
+        reset: Some(ignore_pin),
+        delay_ms: Some(delay_ms),
+        busy_pin_status,
+        reduce_power: None,
+        pa_select: PaSelect::PaSelectAuto,
+        set_board_tcxo: None,
+        set_antenna_pins: None,
+        timer_start: None,
+        timer_stop: None,
+        get_time_ms: None,
+    }
+}
+
+#[test]
+fn sx1276() {
+    let _drivers = drivers();
+    let mut chip = Sx1276Emulator::new();
+    let (_sx12xx, detected) = Sx12xx::detect(chip.bindings(), Sx126xProfile::default()).unwrap();
+    assert_eq!(detected, Chip::Sx1276);
+    // the self-test is all the probe writes; RegDioMapping1 is left alone
+    let writes = chip.take_writes();
+    assert_eq!(&writes[..2], &[(0x0D, 0x55), (0x0D, 0xAA)]);
+}
+
+#[test]
+fn sx1262() {
+    let _drivers = drivers();
+    let chip = Sx126xEmulator::new();
+    let (_sx12xx, detected) = Sx12xx::detect(chip.bindings(), Sx126xProfile::default()).unwrap();
+    assert_eq!(detected, Chip::Sx1262);
+}
+
+#[test]
+fn sx126x_busy_stuck() {
+    let _drivers = drivers();
+    let mut chip = Sx126xEmulator::new();
+    chip.set_busy_stuck(true);
+    let detected = Sx12xx::detect(chip.bindings(), Sx126xProfile::default());
+    assert!(matches!(detected, Err(Error::RadioBusyTimeout)));
+}
+
+#[test]
+fn empty_sockets() {
+    let detect =
+        |bindings| Sx12xx::detect(bindings, Sx126xProfile::default()).map(|(_, detected)| detected);
+    assert!(matches!(
+        detect(empty_socket(miso_low, None)),
+        Err(Error::NoChip)
+    ));
+    assert!(matches!(
+        detect(empty_socket(miso_high, None)),
+        Err(Error::SpiShort)
+    ));
+    // as seen by the SX126x probe on boards that wire BUSY
+    assert!(matches!(
+        detect(empty_socket(miso_low, Some(not_busy))),
+        Err(Error::NoChip)
+    ));
+    assert!(matches!(
+        detect(empty_socket(miso_high, Some(not_busy))),
+        Err(Error::SpiShort)
+    ));
+}
@@ -0,0 +1,200 @@
+// Duty-cycle and dwell-time bookkeeping; the Regulated test needs the mock
+// for its clock and airtime
+use sx12xx::{DutyCycle, Error};
+
+#[test]
+fn eu868_sub_bands() {
+    let mut duty_cycle = DutyCycle::eu868();
+
+    // 1%: 100 ms on air closes the sub-band for 10 s from the start
+    duty_cycle.record(868_100_000, 100, 1_000);
+    for &(frequency, now_ms, wait_ms) in &[
+        (868_100_000, 1_000, 10_000),
+        (868_500_000, 1_000, 10_000),
+        (868_000_000, 5_000, 6_000),
+        (867_900_000, 5_000, 0),
+        (868_100_000, 10_999, 1),
+        (868_100_000, 11_000, 0),
+    ] {
+        assert_eq!(
+            duty_cycle
+                .time_until_allowed(frequency, 50, now_ms)
+                .unwrap(),
+            wait_ms,
+            "{} Hz at {} ms",
+            frequency,
+            now_ms
+        );
+    }
+
+    // 10% and 0.1%
+    duty_cycle.record(869_525_000, 100, 0);
+    assert_eq!(
+        duty_cycle.time_until_allowed(869_525_000, 50, 0).unwrap(),
+        1_000
+    );
+    duty_cycle.record(864_000_000, 10, 0);
+    assert_eq!(
+        duty_cycle.time_until_allowed(864_000_000, 50, 0).unwrap(),
+        10_000
+    );
+    // the other sub-bands are still open
+    assert_eq!(
+        duty_cycle.time_until_allowed(867_100_000, 50, 0).unwrap(),
+        0
+    );
+    assert_eq!(
+        duty_cycle.time_until_allowed(869_800_000, 50, 0).unwrap(),
+        0
+    );
+
+    // between sub-bands nothing is limited
+    duty_cycle.record(869_300_000, 1_000, 0);
+    assert_eq!(
+        duty_cycle.time_until_allowed(869_300_000, 50, 0).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn sub_band_edges() {
+    // adjacent sub-bands share an edge, which belongs to the upper one
+    let mut duty_cycle = DutyCycle::eu868();
+    duty_cycle.record(865_000_000, 10, 0);
+    duty_cycle.record(868_000_000, 20, 0);
+    for &(frequency, wait_ms) in &[
+        (864_999_999, 0),
+        (865_000_000, 1_000),
+        (867_999_999, 1_000),
+        (868_000_000, 2_000),
+        (868_599_999, 2_000),
+        // between sub-bands
+        (868_600_000, 0),
+        (870_000_000, 0),
+    ] {
+        assert_eq!(
+            duty_cycle.time_until_allowed(frequency, 50, 0).unwrap(),
+            wait_ms,
+            "{} Hz",
+            frequency
+        );
+    }
+}
+
+#[test]
+fn sub_band_errors() {
+    let mut duty_cycle = DutyCycle::eu868();
+    assert!(matches!(
+        duty_cycle.add_sub_band(870_000_000, 869_000_000, 100),
+        Err(Error::InvalidSubBand)
+    ));
+    assert!(matches!(
+        duty_cycle.add_sub_band(869_000_000, 869_000_000, 100),
+        Err(Error::InvalidSubBand)
+    ));
+    assert!(matches!(
+        duty_cycle.add_sub_band(915_000_000, 928_000_000, 0),
+        Err(Error::InvalidSubBand)
+    ));
+    // room for 8
+    duty_cycle
+        .add_sub_band(433_050_000, 434_040_000, 10)
+        .unwrap();
+    duty_cycle
+        .add_sub_band(434_040_000, 434_790_000, 100)
+        .unwrap();
+    assert!(matches!(
+        duty_cycle.add_sub_band(915_000_000, 928_000_000, 100),
+        Err(Error::InvalidSubBand)
+    ));
+}
+
+#[test]
+fn dwell_time_400ms() {
+    let mut duty_cycle = DutyCycle::dwell_time_400ms();
+    assert_eq!(
+        duty_cycle.time_until_allowed(923_200_000, 400, 0).unwrap(),
+        0
+    );
+    assert!(matches!(
+        duty_cycle.time_until_allowed(923_200_000, 401, 0),
+        Err(Error::DwellTimeExceeded)
+    ));
+    // no sub-bands, so no off time
+    duty_cycle.record(923_200_000, 400, 0);
+    assert_eq!(
+        duty_cycle.time_until_allowed(923_200_000, 400, 1).unwrap(),
+        0
+    );
+
+    // both limits at once
+    let mut duty_cycle = DutyCycle::eu868();
+    duty_cycle.set_max_dwell_time_ms(Some(400));
+    assert!(matches!(
+        duty_cycle.time_until_allowed(868_100_000, 401, 0),
+        Err(Error::DwellTimeExceeded)
+    ));
+    duty_cycle.set_max_dwell_time_ms(None);
+    assert_eq!(
+        duty_cycle.time_until_allowed(868_100_000, 401, 0).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn clock_wraparound() {
+    let mut duty_cycle = DutyCycle::eu868();
+    let start_ms = u32::MAX - 4_999;
+    // the off time ends at 5_000 ms past the wrap
+    duty_cycle.record(868_100_000, 100, start_ms);
+    for &(now_ms, wait_ms) in &[
+        (start_ms, 10_000),
+        (u32::MAX, 5_001),
+        (0, 5_000),
+        (4_999, 1),
+        (5_000, 0),
+        (6_000, 0),
+    ] {
+        assert_eq!(
+            duty_cycle
+                .time_until_allowed(868_100_000, 50, now_ms)
+                .unwrap(),
+            wait_ms,
+            "at {} ms",
+            now_ms
+        );
+    }
+}
+
+#[cfg(feature = "mock")]
+#[test]
+fn regulated_send() {
+    use sx12xx::mock::Mock;
+    use sx12xx::{LoRaBandwidth, LoRaCodingRate, LoRaSpreadingFactor, Radio, Regulated, Sx12xx};
+
+    let mut mock = Mock::take();
+    let mut sx12xx = Sx12xx::new(Radio::mock(), mock.bindings()).unwrap();
+    sx12xx.set_frequency(868_100_000);
+    sx12xx.configure_lora_tx(
+        14,
+        LoRaBandwidth::_125KHZ,
+        LoRaSpreadingFactor::_7,
+        LoRaCodingRate::_4_5,
+    );
+    let mut radio = Regulated::new(sx12xx, DutyCycle::eu868());
+
+    // 13 bytes at SF7 take 46.3 ms, rounded up to 47
+    radio.send(&mut [0; 13]).unwrap();
+    assert!(mock.take_tx().is_some());
+    assert_eq!(radio.time_until_next_tx(13).unwrap(), 4_700);
+    mock.advance_time(4_000);
+    assert!(matches!(
+        radio.send(&mut [0; 13]),
+        Err(Error::DutyCycleExceeded(700))
+    ));
+    assert!(mock.take_tx().is_none());
+    mock.advance_time(700);
+    assert_eq!(radio.time_until_next_tx(13).unwrap(), 0);
+    radio.send(&mut [0; 13]).unwrap();
+    assert!(mock.take_tx().is_some());
+}
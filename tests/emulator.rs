@@ -0,0 +1,324 @@
+// Sx12xx driving the unmodified C drivers through the register-level models;
+// run with cargo test --target x86_64-unknown-linux-gnu --features mock --tests
+#![cfg(feature = "mock")]
+use core::ffi::c_void;
+use std::sync::{Mutex, MutexGuard};
+use sx12xx::emulator::{Sx126xEmulator, Sx1276Emulator};
+use sx12xx::{
+    Event, LoRaBandwidth, LoRaCodingRate, LoRaSpreadingFactor, PacketQuality, Radio, RadioConfig,
+    Response, Sx12xx,
+};
+
+// only one Sx12xx per chip family may be alive
+static DRIVERS: Mutex<()> = Mutex::new(());
+
+fn drivers() -> MutexGuard<'static, ()> {
+    DRIVERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+mod sx1276 {
+    pub const OPMODE: u8 = 0x01;
+    pub const FRFMSB: u8 = 0x06;
+    pub const FRFMID: u8 = 0x07;
+    pub const FRFLSB: u8 = 0x08;
+    pub const MODEMCONFIG1: u8 = 0x1D;
+    pub const MODEMCONFIG2: u8 = 0x1E;
+
+    pub const LONGRANGEMODE: u8 = 0x80;
+    pub const MODE_MASK: u8 = 0x07;
+    pub const MODE_STANDBY: u8 = 0x01;
+    pub const MODE_TX: u8 = 0x03;
+    pub const MODE_RXCONTINUOUS: u8 = 0x05;
+    pub const MODE_RXSINGLE: u8 = 0x06;
+}
+
+mod sx126x {
+    pub const SET_TX: u8 = 0x83;
+    pub const SET_RX: u8 = 0x82;
+    pub const SET_PACKETTYPE: u8 = 0x8A;
+    pub const SET_MODULATIONPARAMS: u8 = 0x8B;
+    pub const SET_RFFREQUENCY: u8 = 0x86;
+
+    pub const PACKET_TYPE_LORA: u8 = 0x01;
+    pub const LORA_BW_125: u8 = 0x04;
+    pub const LORA_CR_4_5: u8 = 0x01;
+}
+
+// timeouts the drivers asked the board for, as (timer id, ms)
+static TIMERS_STARTED: Mutex<Vec<(u8, u32)>> = Mutex::new(Vec::new());
+
+extern "C" fn timer_start(_context: *mut c_void, timer_id: u8, timeout_ms: u32) {
+    TIMERS_STARTED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((timer_id, timeout_ms));
+}
+
+extern "C" fn timer_stop(_context: *mut c_void, _timer_id: u8) {}
+
+fn configure(sx12xx: &mut Sx12xx, spreading_factor: LoRaSpreadingFactor) {
+    sx12xx.set_frequency(915_000_000);
+    sx12xx.configure_lora_tx(
+        14,
+        LoRaBandwidth::_125KHZ,
+        spreading_factor,
+        LoRaCodingRate::_4_5,
+    );
+    sx12xx.configure_lora_rx(
+        LoRaBandwidth::_125KHZ,
+        spreading_factor,
+        LoRaCodingRate::_4_5,
+    );
+}
+
+fn assert_rx_done(response: Result<Response, sx12xx::Error>, timestamp: u32) {
+    match response {
+        Ok(Response::RxDone(t, PacketQuality::LoRa(quality))) if t == timestamp => {
+            assert_eq!(quality.rssi(), -80);
+            assert_eq!(quality.snr(), 7);
+        }
+        response => panic!("expected RxDone at {}, got {:?}", timestamp, response),
+    }
+}
+
+#[test]
+fn sx1276_lora_tx() {
+    let _drivers = drivers();
+    let mut chip = Sx1276Emulator::new();
+    let mut sx12xx = Sx12xx::new(Radio::sx1276(), chip.bindings()).unwrap();
+    chip.take_writes();
+
+    sx12xx.set_frequency(915_000_000);
+    assert_eq!(
+        chip.take_writes(),
+        [
+            (sx1276::FRFMSB, 0xE4),
+            (sx1276::FRFMID, 0xC0),
+            (sx1276::FRFLSB, 0x00)
+        ]
+    );
+    assert_eq!(chip.frequency(), 915_000_000);
+
+    sx12xx.configure_lora_tx(
+        14,
+        LoRaBandwidth::_125KHZ,
+        LoRaSpreadingFactor::_7,
+        LoRaCodingRate::_4_5,
+    );
+    let writes = chip.take_writes();
+    assert!(writes
+        .iter()
+        .any(|&(address, value)| address == sx1276::OPMODE && value & sx1276::LONGRANGEMODE != 0));
+    // 125 kHz, 4/5, explicit header
+    assert!(writes.contains(&(sx1276::MODEMCONFIG1, 0x72)));
+    // SF7, CRC on
+    assert_eq!(chip.register(sx1276::MODEMCONFIG2) >> 4, 7);
+    assert_ne!(chip.register(sx1276::MODEMCONFIG2) & 0x04, 0);
+
+    sx12xx.send(&mut [0xDE, 0xAD, 0xBE, 0xEF]);
+    let writes = chip.take_writes();
+    assert!(writes
+        .iter()
+        .any(|&(address, value)| address == sx1276::OPMODE
+            && value & (sx1276::LONGRANGEMODE | sx1276::MODE_MASK)
+                == sx1276::LONGRANGEMODE | sx1276::MODE_TX));
+    assert_eq!(chip.take_tx().unwrap(), [0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(
+        chip.register(sx1276::OPMODE) & sx1276::MODE_MASK,
+        sx1276::MODE_STANDBY
+    );
+
+    let event = chip.take_event(42).unwrap();
+    assert!(matches!(event, Event::DIO0(42)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::TxDone(42))
+    ));
+    assert!(chip.take_event(43).is_none());
+}
+
+#[test]
+fn sx1276_lora_rx() {
+    let _drivers = drivers();
+    let mut chip = Sx1276Emulator::new();
+    let mut sx12xx = Sx12xx::new(Radio::sx1276(), chip.bindings()).unwrap();
+    configure(&mut sx12xx, LoRaSpreadingFactor::_9);
+    chip.take_writes();
+
+    sx12xx.set_rx();
+    let writes = chip.take_writes();
+    assert!(writes
+        .iter()
+        .any(|&(address, value)| address == sx1276::OPMODE
+            && value & sx1276::MODE_MASK == sx1276::MODE_RXCONTINUOUS));
+    assert_eq!(chip.register(sx1276::MODEMCONFIG1), 0x72);
+    assert_eq!(chip.register(sx1276::MODEMCONFIG2) >> 4, 9);
+    assert!(chip.take_event(6).is_none());
+
+    assert!(chip.inject_rx(&[1, 2, 3], -80, 7));
+    let event = chip.take_event(7).unwrap();
+    assert!(matches!(event, Event::DIO0(7)));
+    assert_rx_done(sx12xx.handle_event(event), 7);
+    assert_eq!(sx12xx.received(), &[1, 2, 3]);
+    // continuous reception goes on
+    assert_eq!(
+        chip.register(sx1276::OPMODE) & sx1276::MODE_MASK,
+        sx1276::MODE_RXCONTINUOUS
+    );
+}
+
+#[test]
+fn sx1276_lora_rx_single_timeout() {
+    let _drivers = drivers();
+    let mut chip = Sx1276Emulator::new();
+    let mut sx12xx = Sx12xx::new(Radio::sx1276(), chip.bindings()).unwrap();
+    configure(&mut sx12xx, LoRaSpreadingFactor::_9);
+
+    sx12xx.set_rx_single(8).unwrap();
+    assert_eq!(
+        chip.register(sx1276::OPMODE) & sx1276::MODE_MASK,
+        sx1276::MODE_RXSINGLE
+    );
+    assert!(chip.expire_rx());
+    let event = chip.take_event(9).unwrap();
+    assert!(matches!(event, Event::DIO1(9)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::RxTimeout)
+    ));
+
+    // the next reception is continuous again
+    match sx12xx.get_rx_config() {
+        Some(RadioConfig::LoRa(config)) => assert!(config.continuous_rx),
+        config => panic!("expected a LoRa configuration, got {:?}", config),
+    }
+    sx12xx.set_rx();
+    assert_eq!(
+        chip.register(sx1276::OPMODE) & sx1276::MODE_MASK,
+        sx1276::MODE_RXCONTINUOUS
+    );
+    assert!(!chip.expire_rx());
+}
+
+#[test]
+fn sx1276_fsk_tx_with_timers() {
+    let _drivers = drivers();
+    let mut chip = Sx1276Emulator::new();
+    let mut bindings = chip.bindings();
+    bindings.timer_start = Some(timer_start);
+    bindings.timer_stop = Some(timer_stop);
+    let mut sx12xx = Sx12xx::new(Radio::sx1276(), bindings).unwrap();
+    sx12xx.set_frequency(868_300_000);
+    sx12xx.configure_fsk_tx(14, 25_000, 50_000);
+    TIMERS_STARTED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+
+    sx12xx.send(&mut [0xDE, 0xAD, 0xBE, 0xEF]);
+    // SetTx starts the TX timeout whatever its value; a zero one must not
+    // reach the board, or it fires before PacketSent
+    let started = TIMERS_STARTED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    assert!(
+        started.iter().all(|&(_, timeout_ms)| timeout_ms != 0),
+        "timers started: {:?}",
+        started
+    );
+
+    let event = chip.take_event(42).unwrap();
+    assert!(matches!(event, Event::DIO0(42)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::TxDone(42))
+    ));
+}
+
+#[test]
+fn sx126x_lora_tx() {
+    let _drivers = drivers();
+    let mut chip = Sx126xEmulator::new();
+    let mut sx12xx = Sx12xx::new(Radio::sx1262(), chip.bindings()).unwrap();
+    chip.take_commands();
+
+    sx12xx.set_frequency(915_000_000);
+    let commands = chip.take_commands();
+    assert!(commands.contains(&(sx126x::SET_RFFREQUENCY, vec![0x39, 0x30, 0x00, 0x00])));
+    assert_eq!(chip.frequency(), 915_000_000);
+
+    sx12xx.configure_lora_tx(
+        14,
+        LoRaBandwidth::_125KHZ,
+        LoRaSpreadingFactor::_7,
+        LoRaCodingRate::_4_5,
+    );
+    let commands = chip.take_commands();
+    assert!(commands.contains(&(sx126x::SET_PACKETTYPE, vec![sx126x::PACKET_TYPE_LORA])));
+    // SF7, no low data rate optimization
+    assert!(commands.contains(&(
+        sx126x::SET_MODULATIONPARAMS,
+        vec![0x07, sx126x::LORA_BW_125, sx126x::LORA_CR_4_5, 0x00]
+    )));
+
+    sx12xx.send(&mut [0xDE, 0xAD, 0xBE, 0xEF]);
+    let commands = chip.take_commands();
+    assert!(commands.iter().any(|(opcode, _)| *opcode == sx126x::SET_TX));
+    assert_eq!(chip.take_tx().unwrap(), [0xDE, 0xAD, 0xBE, 0xEF]);
+
+    // every interrupt comes out on DIO1
+    let event = chip.take_event(42).unwrap();
+    assert!(matches!(event, Event::DIO1(42)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::TxDone(42))
+    ));
+    assert!(chip.take_event(43).is_none());
+}
+
+#[test]
+fn sx126x_lora_rx() {
+    let _drivers = drivers();
+    let mut chip = Sx126xEmulator::new();
+    let mut sx12xx = Sx12xx::new(Radio::sx1262(), chip.bindings()).unwrap();
+    configure(&mut sx12xx, LoRaSpreadingFactor::_9);
+    chip.take_commands();
+
+    sx12xx.set_rx();
+    let commands = chip.take_commands();
+    assert!(commands.contains(&(sx126x::SET_RX, vec![0xFF, 0xFF, 0xFF])));
+    assert!(chip.take_event(6).is_none());
+
+    assert!(chip.inject_rx(&[1, 2, 3], -80, 7));
+    let event = chip.take_event(7).unwrap();
+    assert!(matches!(event, Event::DIO1(7)));
+    assert_rx_done(sx12xx.handle_event(event), 7);
+    assert_eq!(sx12xx.received(), &[1, 2, 3]);
+}
+
+#[test]
+fn sx126x_lora_rx_single_timeout() {
+    let _drivers = drivers();
+    let mut chip = Sx126xEmulator::new();
+    let mut sx12xx = Sx12xx::new(Radio::sx1262(), chip.bindings()).unwrap();
+    configure(&mut sx12xx, LoRaSpreadingFactor::_9);
+    chip.take_commands();
+
+    sx12xx.set_rx_single(8).unwrap();
+    let commands = chip.take_commands();
+    assert!(commands
+        .iter()
+        .any(|(opcode, params)| *opcode == sx126x::SET_RX && params[..] != [0xFF, 0xFF, 0xFF]));
+    assert!(chip.expire_rx());
+    let event = chip.take_event(9).unwrap();
+    assert!(matches!(event, Event::DIO1(9)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::RxTimeout)
+    ));
+
+    sx12xx.set_rx();
+    let commands = chip.take_commands();
+    assert!(commands.contains(&(sx126x::SET_RX, vec![0xFF, 0xFF, 0xFF])));
+}
@@ -0,0 +1,219 @@
+// Sx12xx and LorawanRadio against the simulated radio; run with
+// cargo test --target x86_64-unknown-linux-gnu --features mock --tests
+#![cfg(feature = "mock")]
+use heapless::consts::*;
+use heapless::Vec;
+use lorawan_device::radio::{
+    Bandwidth, CodingRate, Event as LoraEvent, PhyRxTx, Response as LoraResponse, RfConfig,
+    SpreadingFactor, TxConfig,
+};
+use sx12xx::mock::{Mock, TxFrame};
+use sx12xx::{
+    Event, LoRaBandwidth, LoRaCodingRate, LoRaSpreadingFactor, LorawanRadio, PacketQuality, Radio,
+    RadioConfig, Response, Sx12xx,
+};
+
+// the Mock is taken first so that it outlives the Sx12xx
+fn lora_radio(mock: &Mock) -> Sx12xx {
+    let mut sx12xx = Sx12xx::new(Radio::mock(), mock.bindings()).unwrap();
+    sx12xx.set_frequency(868_100_000);
+    sx12xx.configure_lora_tx(
+        14,
+        LoRaBandwidth::_125KHZ,
+        LoRaSpreadingFactor::_9,
+        LoRaCodingRate::_4_5,
+    );
+    sx12xx.configure_lora_rx(
+        LoRaBandwidth::_125KHZ,
+        LoRaSpreadingFactor::_9,
+        LoRaCodingRate::_4_5,
+    );
+    sx12xx
+}
+
+fn rf_config(frequency: u32, spreading_factor: SpreadingFactor) -> RfConfig {
+    RfConfig {
+        frequency,
+        bandwidth: Bandwidth::_125KHz,
+        spreading_factor,
+        coding_rate: CodingRate::_4_5,
+    }
+}
+
+#[test]
+fn send_ends_with_tx_done() {
+    let mut mock = Mock::take();
+    let mut sx12xx = lora_radio(&mock);
+    mock.advance_time(100);
+    sx12xx.send(&mut [1, 2, 3]);
+    assert_eq!(
+        mock.take_tx(),
+        Some(TxFrame {
+            data: vec![1, 2, 3],
+            frequency: 868_100_000,
+            power: 14,
+            bandwidth: 0,
+            datarate: 9,
+            coderate: 1,
+            iq_inverted: false,
+        })
+    );
+    let event = mock.next_event().unwrap();
+    assert!(matches!(event, Event::DIO0(100)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::TxDone(100))
+    ));
+    assert!(mock.next_event().is_none());
+}
+
+#[test]
+fn inject_rx_ends_with_rx_done() {
+    let mut mock = Mock::take();
+    let mut sx12xx = lora_radio(&mock);
+    sx12xx.set_rx();
+    assert!(mock.is_receiving());
+    mock.advance_time(250);
+    assert!(mock.inject_rx(&[0xCA, 0xFE], -80, 7));
+    let event = mock.next_event().unwrap();
+    assert!(matches!(event, Event::DIO0(250)));
+    match sx12xx.handle_event(event) {
+        Ok(Response::RxDone(250, PacketQuality::LoRa(quality))) => {
+            assert_eq!(quality.rssi(), -80);
+            assert_eq!(quality.snr(), 7);
+        }
+        response => panic!("expected RxDone, got {:?}", response),
+    }
+    assert_eq!(sx12xx.received(), &[0xCA, 0xFE]);
+    // continuous reception goes on
+    assert!(mock.is_receiving());
+}
+
+#[test]
+fn expire_rx_ends_with_rx_timeout() {
+    let mut mock = Mock::take();
+    let mut sx12xx = lora_radio(&mock);
+    assert!(!mock.expire_rx());
+    sx12xx.set_rx_single(8).unwrap();
+    assert!(mock.expire_rx());
+    let event = mock.next_event().unwrap();
+    assert!(matches!(event, Event::DIO1(_)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::RxTimeout)
+    ));
+    assert!(!mock.is_receiving());
+    // the single reception leaves the stored configuration alone
+    match sx12xx.get_rx_config() {
+        Some(RadioConfig::LoRa(config)) => assert!(config.continuous_rx),
+        config => panic!("expected a LoRa configuration, got {:?}", config),
+    }
+}
+
+#[test]
+fn rx_timeout_expires_with_time() {
+    let mut mock = Mock::take();
+    let mut sx12xx = lora_radio(&mock);
+    sx12xx.set_rx_timeout(500).unwrap();
+    mock.advance_time(499);
+    assert!(mock.next_event().is_none());
+    mock.advance_time(1);
+    let event = mock.next_event().unwrap();
+    assert!(matches!(event, Event::DIO1(500)));
+    assert!(matches!(
+        sx12xx.handle_event(event),
+        Ok(Response::RxTimeout)
+    ));
+    assert!(!mock.is_receiving());
+}
+
+#[test]
+fn lorawan_join_and_uplink() {
+    let mut mock = Mock::take();
+    let sx12xx = Sx12xx::new(Radio::mock(), mock.bindings()).unwrap();
+    let mut radio = LorawanRadio::new(sx12xx);
+
+    // join request
+    let mut join_request: Vec<u8, U256> = Vec::from_slice(&[0x00; 23]).unwrap();
+    let tx_config = TxConfig {
+        pw: 14,
+        rf: rf_config(903_900_000, SpreadingFactor::_10),
+    };
+    let response = radio.handle_event(LoraEvent::TxRequest(tx_config, &mut join_request));
+    assert!(matches!(response, Ok(LoraResponse::Txing)));
+    let frame = mock.take_tx().unwrap();
+    assert_eq!(frame.data, [0x00; 23]);
+    assert_eq!(frame.frequency, 903_900_000);
+    assert_eq!(frame.datarate, 10);
+    let event = mock.next_event().unwrap();
+    let response = radio.handle_event(LoraEvent::PhyEvent(event));
+    assert!(matches!(response, Ok(LoraResponse::TxDone(0))));
+
+    // join accept in RX1
+    mock.advance_time(4_500);
+    let rx_config = rf_config(923_300_000, SpreadingFactor::_10);
+    let response = radio.handle_event(LoraEvent::RxRequest(rx_config));
+    assert!(matches!(response, Ok(LoraResponse::Rxing)));
+    assert!(mock.is_receiving());
+    assert_eq!(mock.frequency(), 923_300_000);
+    let join_accept = [0x20; 17];
+    assert!(mock.inject_rx(&join_accept, -80, 7));
+    let event = mock.next_event().unwrap();
+    match radio.handle_event(LoraEvent::PhyEvent(event)) {
+        Ok(LoraResponse::RxDone(quality)) => {
+            assert_eq!(quality.rssi(), -80);
+            assert_eq!(quality.snr(), 7);
+        }
+        _ => panic!("expected RxDone"),
+    }
+    assert_eq!(&radio.get_received_packet()[..], &join_accept[..]);
+    assert!(!mock.is_receiving());
+
+    // first uplink
+    mock.advance_time(1_000);
+    let mut uplink: Vec<u8, U256> = Vec::from_slice(&[0x40, 1, 2, 3]).unwrap();
+    let tx_config = TxConfig {
+        pw: 14,
+        rf: rf_config(904_100_000, SpreadingFactor::_7),
+    };
+    let response = radio.handle_event(LoraEvent::TxRequest(tx_config, &mut uplink));
+    assert!(matches!(response, Ok(LoraResponse::Txing)));
+    let frame = mock.take_tx().unwrap();
+    assert_eq!(frame.data, [0x40, 1, 2, 3]);
+    assert_eq!(frame.frequency, 904_100_000);
+    assert_eq!(frame.datarate, 7);
+    let event = mock.next_event().unwrap();
+    let response = radio.handle_event(LoraEvent::PhyEvent(event));
+    assert!(matches!(response, Ok(LoraResponse::TxDone(5_500))));
+
+    // nothing in RX1: the window closes by itself
+    let rx_config = rf_config(923_900_000, SpreadingFactor::_7);
+    let response = radio.handle_event(LoraEvent::RxRequest(rx_config));
+    assert!(matches!(response, Ok(LoraResponse::Rxing)));
+    mock.advance_time(800);
+    let event = mock.next_event().unwrap();
+    let response = radio.handle_event(LoraEvent::PhyEvent(event));
+    assert!(matches!(response, Ok(LoraResponse::Idle)));
+    assert!(!mock.is_receiving());
+}
+
+#[test]
+fn lorawan_rx_window_without_timers() {
+    let mut mock = Mock::take();
+    let mut bindings = mock.bindings();
+    bindings.timer_start = None;
+    bindings.timer_stop = None;
+    let sx12xx = Sx12xx::new(Radio::mock(), bindings).unwrap();
+    let mut radio = LorawanRadio::new(sx12xx);
+
+    // nothing closes the window but lorawan-device cancelling it
+    let rx_config = rf_config(923_300_000, SpreadingFactor::_10);
+    let response = radio.handle_event(LoraEvent::RxRequest(rx_config));
+    assert!(matches!(response, Ok(LoraResponse::Rxing)));
+    mock.advance_time(10_000);
+    assert!(mock.next_event().is_none());
+    assert!(mock.is_receiving());
+    let response = radio.handle_event(LoraEvent::CancelRx);
+    assert!(matches!(response, Ok(LoraResponse::Idle)));
+    assert!(!mock.is_receiving());
+}